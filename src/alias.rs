@@ -0,0 +1,99 @@
+use super::{LiveCell, Store, Token};
+use std::any::Any;
+
+/// A read-only token to a value, which can be revoked on its own
+///
+/// It is created using `Token::alias`, and only gives shared access to the
+/// value, through `Store::get_alias`. Its clones share its revocation, but
+/// revoking it leaves the original token and the other aliases untouched.
+pub struct AliasToken<V> {
+    token: Token<V>,
+    live: LiveCell,
+}
+
+impl<V> Token<V> {
+    /// Create a read-only alias of this token
+    pub fn alias(&self) -> AliasToken<V> {
+        AliasToken {
+            token: self.clone(),
+            live: LiveCell::new(true),
+        }
+    }
+}
+
+impl<V> AliasToken<V> {
+    /// Check wether this alias was not revoked, and its value not removed
+    pub fn is_valid(&self) -> bool {
+        self.live.get() && self.token.is_valid()
+    }
+
+    /// Revoke this alias and its clones
+    ///
+    /// The value can no longer be accessed through them.
+    pub fn revoke(&self) {
+        self.live.set(false);
+    }
+}
+
+impl<V> Clone for AliasToken<V> {
+    fn clone(&self) -> AliasToken<V> {
+        AliasToken {
+            token: self.token.clone(),
+            live: self.live.clone(),
+        }
+    }
+}
+
+impl Store {
+    /// Access a value through an alias token
+    ///
+    /// Panics if the alias was revoked, or if the value was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn get_alias<V: Any + 'static>(&self, alias: &AliasToken<V>) -> &V {
+        if !alias.live.get() {
+            panic!("Attempted to access a value through a revoked alias.");
+        }
+        self.get(&alias.token)
+    }
+
+    /// Try to access a value through an alias token
+    ///
+    /// Returns `None` if the alias was revoked, or if the value was removed.
+    pub fn try_get_alias<V: Any + 'static>(&self, alias: &AliasToken<V>) -> Option<&V> {
+        if !alias.live.get() {
+            return None;
+        }
+        self.try_get(&alias.token).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alias() {
+        let mut store = Store::new();
+        let token = store.insert(1);
+        let first = token.alias();
+        let second = token.alias();
+        let clone = first.clone();
+        assert_eq!(*store.get_alias(&first), 1);
+        first.revoke();
+        assert!(!clone.is_valid());
+        assert_eq!(store.try_get_alias(&clone), None);
+        assert_eq!(store.try_get_alias(&second), Some(&1));
+        assert_eq!(*store.get(&token), 1);
+        store.remove(token);
+        assert!(!second.is_valid());
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempted to access a value through a revoked alias.")]
+    fn revoked_alias() {
+        let mut store = Store::new();
+        let alias = store.insert(1).alias();
+        alias.revoke();
+        store.get_alias(&alias);
+    }
+}
@@ -0,0 +1,72 @@
+use super::{Store, Token};
+use std::ops::Deref;
+use std::rc::Rc;
+
+/// A byte buffer stored outside of the store slots
+///
+/// The bytes live in a reference-counted buffer, so inserting them does not
+/// copy them, and they can be shared with code outside the store using
+/// `shared`.
+#[derive(Clone)]
+pub struct Blob {
+    bytes: Rc<[u8]>,
+}
+
+impl Blob {
+    /// Create a blob from its bytes
+    pub fn new<B: Into<Rc<[u8]>>>(bytes: B) -> Blob {
+        Blob { bytes: bytes.into() }
+    }
+
+    /// Get a handle to the buffer holding the bytes of this blob
+    pub fn shared(&self) -> Rc<[u8]> {
+        self.bytes.clone()
+    }
+}
+
+impl Deref for Blob {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl AsRef<[u8]> for Blob {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Store {
+    /// Insert a byte buffer in this store
+    ///
+    /// The bytes are kept in a `Blob`, outside of the slot of the store.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn insert_blob<B: Into<Rc<[u8]>>>(&mut self, bytes: B) -> Token<Blob> {
+        self.insert(Blob::new(bytes))
+    }
+
+    /// Access the bytes of a blob of this store
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn get_blob(&self, token: &Token<Blob>) -> &[u8] {
+        &self.get(token).bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blobs() {
+        let mut store = Store::new();
+        let bytes: Rc<[u8]> = Rc::from(&b"I like trains"[..]);
+        let token = store.insert_blob(bytes.clone());
+        assert_eq!(store.get_blob(&token), b"I like trains");
+        assert!(Rc::ptr_eq(&store.get(&token).shared(), &bytes));
+        let token = store.insert_blob(vec![1, 2, 3]);
+        assert_eq!(store.get(&token).len(), 3);
+    }
+}
@@ -0,0 +1,111 @@
+use super::{Store, Token};
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+/// A token bound to its store
+///
+/// This token keeps a weak handle to a shared `Rc<RefCell<Store>>`, and can
+/// thus access its value without being given the store explicitly.
+///
+/// It is created using `Token::bind`.
+pub struct BoundToken<V> {
+    token: Token<V>,
+    store: Weak<RefCell<Store>>,
+}
+
+impl<V> Token<V> {
+    /// Bind this token to the shared store it belongs to
+    ///
+    /// The returned `BoundToken` can access its value using `with` and
+    /// `with_mut`, without needing a reference to the store.
+    pub fn bind(self, store: &Rc<RefCell<Store>>) -> BoundToken<V> {
+        BoundToken {
+            token: self,
+            store: Rc::downgrade(store),
+        }
+    }
+}
+
+impl<V: Any + 'static> BoundToken<V> {
+    /// Access the value of this token
+    ///
+    /// Panics if the store was dropped, if it is currently mutably borrowed,
+    /// or if the value was removed.
+    pub fn with<T, F: FnOnce(&V) -> T>(&self, f: F) -> T {
+        let store = self.upgrade();
+        let store = store.borrow();
+        f(store.get(&self.token))
+    }
+
+    /// Mutably access the value of this token
+    ///
+    /// Panics if the store was dropped, if it is currently borrowed,
+    /// or if the value was removed.
+    pub fn with_mut<T, F: FnOnce(&mut V) -> T>(&self, f: F) -> T {
+        let store = self.upgrade();
+        let mut store = store.borrow_mut();
+        f(store.get_mut(&self.token))
+    }
+
+    fn upgrade(&self) -> Rc<RefCell<Store>> {
+        match self.store.upgrade() {
+            Some(store) => store,
+            None => panic!("Attempted to access a value of a Store that was dropped!"),
+        }
+    }
+}
+
+impl<V> BoundToken<V> {
+    /// Check wether this token is still valid
+    ///
+    /// A bound token is valid if its store is still alive and its
+    /// value was not removed.
+    pub fn is_valid(&self) -> bool {
+        self.token.is_valid() && self.store.upgrade().is_some()
+    }
+
+    /// Access the underlying token
+    pub fn token(&self) -> &Token<V> {
+        &self.token
+    }
+
+    /// Unbind this token, retrieving the underlying token
+    pub fn unbind(self) -> Token<V> {
+        self.token
+    }
+}
+
+impl<V> Clone for BoundToken<V> {
+    fn clone(&self) -> BoundToken<V> {
+        BoundToken {
+            token: self.token.clone(),
+            store: self.store.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_and_with_mut() {
+        let store = Rc::new(RefCell::new(Store::new()));
+        let token = store.borrow_mut().insert(42).bind(&store);
+        token.with_mut(|v| *v += 1);
+        assert_eq!(token.with(|v| *v), 43);
+        assert!(token.is_valid());
+        drop(store);
+        assert!(!token.is_valid());
+    }
+
+    #[test]
+    #[should_panic]
+    fn no_access_dropped_store() {
+        let store = Rc::new(RefCell::new(Store::new()));
+        let token = store.borrow_mut().insert(42).bind(&store);
+        drop(store);
+        token.with(|_| {});
+    }
+}
@@ -0,0 +1,140 @@
+use super::{Store, Token};
+use std::any::Any;
+use std::marker::PhantomData;
+
+// An invariant lifetime, used to tie tokens to a single store
+type Brand<'id> = PhantomData<fn(&'id ()) -> &'id ()>;
+
+/// A store branded with a unique lifetime
+///
+/// This store is created by `Store::scope`, and its tokens carry the same
+/// invariant lifetime `'id` as the store itself. As each call to `Store::scope`
+/// creates a new lifetime, using the tokens of a store with another store
+/// is a compile error:
+///
+/// ```compile_fail
+/// # extern crate token_store;
+/// # use token_store::Store;
+/// # fn main() {
+/// Store::scope(|mut store1| {
+///     Store::scope(|store2| {
+///         let token = store1.insert(42);
+///         store2.get(&token);
+///     });
+/// });
+/// # }
+/// ```
+///
+/// As a consequence, accessing a value only needs to check that it was not
+/// removed, the check that the token was created by this store is skipped
+/// entirely.
+pub struct BrandedStore<'id> {
+    store: Store,
+    _brand: Brand<'id>,
+}
+
+/// A token of a `BrandedStore`
+pub struct BrandedToken<'id, V> {
+    token: Token<V>,
+    _brand: Brand<'id>,
+}
+
+impl<'id, V> Clone for BrandedToken<'id, V> {
+    fn clone(&self) -> BrandedToken<'id, V> {
+        BrandedToken {
+            token: self.token.clone(),
+            _brand: PhantomData,
+        }
+    }
+}
+
+impl<'id, V> BrandedToken<'id, V> {
+    /// Check wether this token is still valid
+    pub fn is_valid(&self) -> bool {
+        self.token.is_valid()
+    }
+}
+
+impl Store {
+    /// Create a branded store, only usable within the provided closure
+    ///
+    /// See `BrandedStore` for details.
+    pub fn scope<T, F>(f: F) -> T
+    where
+        F: for<'id> FnOnce(BrandedStore<'id>) -> T,
+    {
+        f(BrandedStore {
+            store: Store::new(),
+            _brand: PhantomData,
+        })
+    }
+}
+
+impl<'id> BrandedStore<'id> {
+    /// Insert a new value in this store
+    pub fn insert<V: Any + 'static>(&mut self, value: V) -> BrandedToken<'id, V> {
+        BrandedToken {
+            token: self.store.insert(value),
+            _brand: PhantomData,
+        }
+    }
+
+    /// Access value previously inserted in this store
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    pub fn get<V: Any + 'static>(&self, token: &BrandedToken<'id, V>) -> &V {
+        self.check(token);
+        // The brand guarantees the token comes from this store, and as long as
+        // the token is alive its slot holds a value of type V.
+        unsafe { self.store.get_unchecked(&token.token) }
+    }
+
+    /// Mutably access value previously inserted in this store
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    pub fn get_mut<V: Any + 'static>(&mut self, token: &BrandedToken<'id, V>) -> &mut V {
+        self.check(token);
+        unsafe { self.store.get_unchecked_mut(&token.token) }
+    }
+
+    /// Remove a value previously inserted in this store
+    ///
+    /// Panics if the provided token corresponds to a value that was already
+    /// removed.
+    pub fn remove<V: Any + 'static>(&mut self, token: BrandedToken<'id, V>) -> V {
+        self.store.remove(token.token)
+    }
+
+    fn check<V>(&self, token: &BrandedToken<'id, V>) {
+        if !token.token.live.get() {
+            panic!("Attempted to access a state value that was already removed!");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn branded_access() {
+        let len = Store::scope(|mut store| {
+            let token = store.insert(String::from("I like trains"));
+            let token2 = store.insert(42);
+            store.get_mut(&token).push_str(", and cars too!");
+            assert_eq!(store.remove(token2), 42);
+            store.get(&token).len()
+        });
+        assert_eq!(len, 28);
+    }
+
+    #[test]
+    #[should_panic]
+    fn no_access_removed() {
+        Store::scope(|mut store| {
+            let token = store.insert(42);
+            store.remove(token.clone());
+            store.get(&token);
+        });
+    }
+}
@@ -0,0 +1,108 @@
+use super::{AccessFailure, ReuseOrder, Store};
+use std::any::Any;
+
+/// A builder to configure a store at construction
+///
+/// It is created by `Store::builder`. Each method corresponds to a setter
+/// of `Store`, see their documentation for details.
+pub struct StoreBuilder {
+    store: Store,
+}
+
+impl StoreBuilder {
+    /// Limit the number of values of the store, see `Store::set_max_len`
+    pub fn max_len(mut self, max: usize) -> StoreBuilder {
+        self.store.set_max_len(Some(max));
+        self
+    }
+
+    /// Evict values past a number of values, see `Store::set_eviction_limit`
+    pub fn eviction_limit(mut self, limit: usize) -> StoreBuilder {
+        self.store.set_eviction_limit(Some(limit));
+        self
+    }
+
+    /// Set wether removed slots are reused, see `Store::set_slot_reuse`
+    pub fn slot_reuse(mut self, reuse: bool) -> StoreBuilder {
+        self.store.set_slot_reuse(reuse);
+        self
+    }
+
+    /// Set the order in which removed slots are reused, see
+    /// `Store::set_reuse_order`
+    pub fn reuse_order(mut self, order: ReuseOrder) -> StoreBuilder {
+        self.store.set_reuse_order(order);
+        self
+    }
+
+    /// Enable constant-time reverse lookups, see
+    /// `Store::enable_reverse_lookup`
+    pub fn reverse_lookup(mut self) -> StoreBuilder {
+        self.store.enable_reverse_lookup();
+        self
+    }
+
+    /// Limit the number of values of type `V`, see `Store::set_quota`
+    pub fn quota<V: Any + 'static>(mut self, max: usize) -> StoreBuilder {
+        self.store.set_quota::<V>(Some(max));
+        self
+    }
+
+    /// Set the quota violation handler, see `Store::set_quota_handler`
+    pub fn quota_handler<F: FnMut(&'static str) + 'static>(mut self, handler: F) -> StoreBuilder {
+        self.store.set_quota_handler(handler);
+        self
+    }
+
+    /// Set the failed access hook, see `Store::set_access_hook`
+    pub fn access_hook<F: Fn(&AccessFailure) + 'static>(mut self, hook: F) -> StoreBuilder {
+        self.store.set_access_hook(hook);
+        self
+    }
+
+    /// Register `V` as clonable, see `Store::register_clone`
+    pub fn clonable<V: Any + Clone + 'static>(mut self) -> StoreBuilder {
+        self.store.register_clone::<V>();
+        self
+    }
+
+    /// Register `V` as comparable, see `Store::register_eq`
+    pub fn comparable<V: Any + PartialEq + 'static>(mut self) -> StoreBuilder {
+        self.store.register_eq::<V>();
+        self
+    }
+
+    /// Create the configured store
+    pub fn build(self) -> Store {
+        self.store
+    }
+}
+
+impl Store {
+    /// Start configuring a new store
+    pub fn builder() -> StoreBuilder {
+        StoreBuilder { store: Store::new() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder() {
+        let mut store = Store::builder()
+            .max_len(3)
+            .quota::<u8>(1)
+            .slot_reuse(false)
+            .clonable::<i32>()
+            .build();
+        let token = store.insert(0u8);
+        assert!(store.try_insert(1u8).is_err());
+        store.remove(token);
+        assert_eq!(store.insert(1i32).id, 1);
+        store.insert(2i32);
+        store.insert(3i32);
+        assert!(store.try_insert(4i32).is_err());
+    }
+}
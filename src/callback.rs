@@ -0,0 +1,92 @@
+use super::{Store, StoreProxy, Token};
+
+type CallbackFn<Args, Ret> = Box<dyn FnMut(&mut StoreProxy, Args) -> Ret>;
+
+/// A callback stored in a store
+///
+/// It is created by `Store::insert_callback`, and invoked with `dispatch`.
+pub struct Callback<Args, Ret = ()> {
+    f: CallbackFn<Args, Ret>,
+}
+
+impl Store {
+    /// Insert a callback in this store
+    ///
+    /// The callback can later be invoked with `dispatch`, and is then given a
+    /// `StoreProxy` to access the other values of the store.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn insert_callback<Args, Ret, F>(&mut self, f: F) -> Token<Callback<Args, Ret>>
+    where
+        Args: 'static,
+        Ret: 'static,
+        F: FnMut(&mut StoreProxy, Args) -> Ret + 'static,
+    {
+        self.insert(Callback { f: Box::new(f) })
+    }
+
+    /// Invoke a callback of this store
+    ///
+    /// While it runs, the callback can insert, remove and access the other
+    /// values of the store, and dispatch other callbacks.
+    ///
+    /// Panics if the provided token corresponds to a callback that was
+    /// removed.
+    pub fn dispatch<Args: 'static, Ret: 'static>(&mut self, token: &Token<Callback<Args, Ret>>, args: Args)
+                                                  -> Ret {
+        self.as_proxy().dispatch(token, args)
+    }
+}
+
+impl<'store> StoreProxy<'store> {
+    /// Invoke a callback of the proxified store
+    ///
+    /// Panics if the provided token corresponds to a callback that was
+    /// removed, or if this callback is already running.
+    ///
+    /// See `Store::dispatch` for full documentation.
+    pub fn dispatch<Args: 'static, Ret: 'static>(&mut self, token: &Token<Callback<Args, Ret>>, args: Args)
+                                                  -> Ret {
+        self.with_value(token, |proxy, callback| (callback.f)(proxy, args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dispatch() {
+        let mut store = Store::new();
+        let counter = store.insert(0);
+        let add = {
+            let counter = counter.clone();
+            store.insert_callback(move |proxy, n: i32| {
+                *proxy.get_mut(&counter) += n;
+                proxy.insert(n);
+                *proxy.get(&counter)
+            })
+        };
+        let twice = {
+            let add = add.clone();
+            store.insert_callback(move |proxy, n: i32| {
+                proxy.dispatch(&add, n);
+                proxy.dispatch(&add, n)
+            })
+        };
+        assert_eq!(store.dispatch(&add, 1), 1);
+        assert_eq!(store.dispatch(&twice, 2), 5);
+        assert_eq!(store.values_cloned::<i32>(), vec![5, 1, 2, 2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn no_reentrant_dispatch() {
+        let mut store = Store::new();
+        let token = store.insert_callback(|_, _: ()| ());
+        let inner = token.clone();
+        let outer = store.insert_callback(move |proxy, _: ()| {
+            proxy.dispatch(&inner, ());
+        });
+        store.with_value(&token, |proxy, _| proxy.dispatch(&outer, ()));
+    }
+}
@@ -0,0 +1,178 @@
+//! C API
+//!
+//! This module exposes the store through a C-compatible interface, so that
+//! non-Rust components can share a store with Rust code. It is only available
+//! when the `capi` cargo feature is enabled.
+//!
+//! From C, the store is an opaque `token_store_t`. Values are opaque pointers,
+//! optionnaly associated with a destructor callback that the store will invoke
+//! when the value is removed, or when the store is destroyed. Values are
+//! designated by numeric handles rather than tokens.
+//!
+//! Panics never unwind into C: each function catches them, and reports them
+//! with its error value instead.
+#![allow(non_camel_case_types)]
+
+use super::Store;
+use std::os::raw::{c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
+
+/// An opaque store, to be manipulated through the `token_store_*` functions
+pub struct token_store_t {
+    store: Store,
+}
+
+/// A numeric handle designating a value of a `token_store_t`
+pub type token_store_handle_t = u64;
+
+/// The handle returned by `token_store_insert` when it fails
+///
+/// It never designates a value, as that would take a store of 2^32 slots.
+pub const TOKEN_STORE_INVALID_HANDLE: token_store_handle_t = u64::MAX;
+
+/// A destructor callback, invoked with the stored pointer when its value is destroyed
+pub type token_store_destructor_t = Option<unsafe extern "C" fn(*mut c_void)>;
+
+struct CValue {
+    ptr: *mut c_void,
+    destructor: token_store_destructor_t,
+}
+
+impl Drop for CValue {
+    fn drop(&mut self) {
+        if let Some(destructor) = self.destructor {
+            unsafe { destructor(self.ptr) }
+        }
+    }
+}
+
+// Runs the body of a function of the C API, returning `error` if it panics
+fn catch<T, F: FnOnce() -> T>(error: T, f: F) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(error)
+}
+
+/// Create a new store
+///
+/// The returned store must be freed with `token_store_destroy`. Returns `NULL`
+/// if the store could not be created.
+#[no_mangle]
+pub extern "C" fn token_store_new() -> *mut token_store_t {
+    catch(::std::ptr::null_mut(), || {
+        Box::into_raw(Box::new(token_store_t { store: Store::new() }))
+    })
+}
+
+/// Destroy a store
+///
+/// The destructors of all values still present in the store are invoked. If
+/// destroying one of the Rust values fails, the remaining ones are leaked.
+///
+/// # Safety
+///
+/// `store` must have been created by `token_store_new` and not destroyed yet.
+#[no_mangle]
+pub unsafe extern "C" fn token_store_destroy(store: *mut token_store_t) {
+    if !store.is_null() {
+        catch((), || drop(Box::from_raw(store)));
+    }
+}
+
+/// Insert a pointer in the store
+///
+/// Returns the handle designating this value, or `TOKEN_STORE_INVALID_HANDLE`
+/// if it could not be inserted, in which case the destructor was invoked.
+/// `destructor` may be `NULL`.
+///
+/// # Safety
+///
+/// `store` must be a valid store created by `token_store_new`.
+#[no_mangle]
+pub unsafe extern "C" fn token_store_insert(store: *mut token_store_t, ptr: *mut c_void,
+                                            destructor: token_store_destructor_t)
+                                            -> token_store_handle_t {
+    let store = &mut (*store).store;
+    catch(TOKEN_STORE_INVALID_HANDLE, || store.insert(CValue { ptr, destructor }).to_handle())
+}
+
+/// Retrieve the pointer designated by a handle
+///
+/// Returns `NULL` if the handle does not designate a value of this store, or
+/// if the value could not be accessed.
+///
+/// # Safety
+///
+/// `store` must be a valid store created by `token_store_new`.
+#[no_mangle]
+pub unsafe extern "C" fn token_store_get(store: *const token_store_t, handle: token_store_handle_t)
+                                         -> *mut c_void {
+    let store = &(*store).store;
+    catch(::std::ptr::null_mut(), || match store.token_from_handle::<CValue>(handle) {
+        Some(token) => store.get(&token).ptr,
+        None => ::std::ptr::null_mut(),
+    })
+}
+
+/// Remove the value designated by a handle, invoking its destructor
+///
+/// Returns 1 if a value was removed, 0 if the handle does not designate a value
+/// of this store, and -1 if the removal failed.
+///
+/// # Safety
+///
+/// `store` must be a valid store created by `token_store_new`.
+#[no_mangle]
+pub unsafe extern "C" fn token_store_remove(store: *mut token_store_t, handle: token_store_handle_t)
+                                            -> c_int {
+    let store = &mut (*store).store;
+    catch(-1, || match store.token_from_handle::<CValue>(handle) {
+        Some(token) => {
+            drop(store.remove(token));
+            1
+        }
+        None => 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    thread_local!(static DESTROYED: Cell<usize> = const { Cell::new(0) });
+
+    unsafe extern "C" fn count_destroy(_: *mut c_void) {
+        DESTROYED.with(|d| d.set(d.get() + 1));
+    }
+
+    #[test]
+    fn insert_get_remove() {
+        let mut value = 42u32;
+        let ptr = &mut value as *mut u32 as *mut c_void;
+        unsafe {
+            let store = token_store_new();
+            let handle = token_store_insert(store, ptr, Some(count_destroy));
+            let handle2 = token_store_insert(store, ptr, Some(count_destroy));
+            assert_eq!(token_store_get(store, handle), ptr);
+            assert_eq!(token_store_remove(store, handle), 1);
+            assert_eq!(DESTROYED.with(|d| d.get()), 1);
+            assert!(token_store_get(store, handle).is_null());
+            assert_eq!(token_store_remove(store, handle), 0);
+            assert_eq!(token_store_get(store, handle2), ptr);
+            token_store_destroy(store);
+            assert_eq!(DESTROYED.with(|d| d.get()), 2);
+        }
+    }
+
+    #[test]
+    fn no_unwinding() {
+        unsafe {
+            let store = token_store_new();
+            (*store).store.set_max_len(Some(0));
+            let handle = token_store_insert(store, ::std::ptr::null_mut(), None);
+            assert_eq!(handle, TOKEN_STORE_INVALID_HANDLE);
+            assert!(token_store_get(store, handle).is_null());
+            assert_eq!(token_store_remove(store, handle), 0);
+            token_store_destroy(store);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::TryReserveError;
+use std::ops::{Index, IndexMut};
+
+const CHUNK_SIZE: usize = 4096;
+// The capacity of the first chunk once it is allocated
+const MIN_CAPACITY: usize = 8;
+
+/// A growable array storing its elements in fixed-size chunks
+///
+/// Unlike a `Vec`, growing it past its first chunk never moves the existing
+/// elements: a new chunk is allocated once the last one is full. This avoids
+/// the latency spikes of reallocating and copying a huge array. The first
+/// chunk grows geometrically up to the chunk size, so small arrays stay
+/// small.
+pub struct ChunkedVec<T> {
+    chunks: Vec<Vec<T>>,
+    len: usize,
+}
+
+impl<T> ChunkedVec<T> {
+    pub fn new() -> ChunkedVec<T> {
+        ChunkedVec {
+            chunks: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn push(&mut self, value: T) {
+        let last = self.len / CHUNK_SIZE;
+        if last == self.chunks.len() {
+            let capacity = if last == 0 { 0 } else { CHUNK_SIZE };
+            self.chunks.push(Vec::with_capacity(capacity));
+        }
+        let chunk = &mut self.chunks[last];
+        if chunk.len() == chunk.capacity() {
+            // only the first chunk can be partially allocated
+            let capacity = (chunk.capacity() * 2).clamp(MIN_CAPACITY, CHUNK_SIZE);
+            chunk.reserve_exact(capacity - chunk.len());
+        }
+        chunk.push(value);
+        self.len += 1;
+    }
+
+    // Allocates the chunks needed for `additional` more elements
+    //
+    // The new chunks are pushed empty, `push` fills them in order.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let total = self.len.saturating_add(additional);
+        if total == 0 {
+            return Ok(());
+        }
+        let needed = total.div_ceil(CHUNK_SIZE);
+        if self.chunks.is_empty() {
+            self.chunks.try_reserve(needed)?;
+            self.chunks.push(Vec::new());
+        }
+        let first = &mut self.chunks[0];
+        let wanted = total.min(CHUNK_SIZE);
+        if first.capacity() < wanted {
+            first.try_reserve_exact(wanted - first.len())?;
+        }
+        if needed <= self.chunks.len() {
+            return Ok(());
+        }
+        self.chunks.try_reserve(needed - self.chunks.len())?;
+        while self.chunks.len() < needed {
+            let mut chunk = Vec::new();
+            chunk.try_reserve_exact(CHUNK_SIZE)?;
+            self.chunks.push(chunk);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.chunks
+            .get(index / CHUNK_SIZE)
+            .and_then(|chunk| chunk.get(index % CHUNK_SIZE))
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.chunks
+            .get_mut(index / CHUNK_SIZE)
+            .and_then(|chunk| chunk.get_mut(index % CHUNK_SIZE))
+    }
+
+    #[inline]
+    pub unsafe fn get_unchecked(&self, index: usize) -> &T {
+        self.chunks
+            .get_unchecked(index / CHUNK_SIZE)
+            .get_unchecked(index % CHUNK_SIZE)
+    }
+
+    #[inline]
+    pub unsafe fn get_unchecked_mut(&mut self, index: usize) -> &mut T {
+        self.chunks
+            .get_unchecked_mut(index / CHUNK_SIZE)
+            .get_unchecked_mut(index % CHUNK_SIZE)
+    }
+}
+
+impl<T> Index<usize> for ChunkedVec<T> {
+    type Output = T;
+    fn index(&self, index: usize) -> &T {
+        &self.chunks[index / CHUNK_SIZE][index % CHUNK_SIZE]
+    }
+}
+
+impl<T> IndexMut<usize> for ChunkedVec<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        &mut self.chunks[index / CHUNK_SIZE][index % CHUNK_SIZE]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn growth_does_not_move() {
+        let mut vec = ChunkedVec::new();
+        vec.push(0usize);
+        assert!(vec.chunks[0].capacity() < CHUNK_SIZE);
+        for i in 1..CHUNK_SIZE {
+            vec.push(i);
+        }
+        let first = &vec[0] as *const usize;
+        for i in CHUNK_SIZE..(3 * CHUNK_SIZE + 1) {
+            vec.push(i);
+        }
+        assert_eq!(first, &vec[0] as *const usize);
+        assert_eq!(vec.len(), 3 * CHUNK_SIZE + 1);
+        assert_eq!(vec.get(2 * CHUNK_SIZE + 3), Some(&(2 * CHUNK_SIZE + 3)));
+        assert_eq!(vec.get(3 * CHUNK_SIZE + 1), None);
+    }
+}
@@ -0,0 +1,125 @@
+use super::{caller_provenance, AnyToken, Provenance, Store};
+use std::any::{Any, TypeId};
+
+// Clones a boxed value whose concrete type is known to be V
+pub(crate) type CloneFn = fn(&dyn Any) -> Box<dyn Any>;
+
+fn clone_value<V: Any + Clone + 'static>(value: &dyn Any) -> Box<dyn Any> {
+    Box::new(value.downcast_ref::<V>().unwrap().clone())
+}
+
+impl Store {
+    /// Register `V` as a clonable type
+    ///
+    /// The store cannot know by itself how to clone the values it holds, as
+    /// they are type-erased. Registering their types allows them to be copied
+    /// to another store by `clone_subset`.
+    pub fn register_clone<V: Any + Clone + 'static>(&mut self) {
+        self.cloners.insert(TypeId::of::<V>(), clone_value::<V>);
+    }
+
+    /// Clone some values of this store into a new store
+    ///
+    /// The clones keep the slot index and the generation of their original,
+    /// so the raw parts or handles of the original tokens can be resolved in
+    /// the new store. The stable identifiers of the values, and the clone and
+    /// comparison registrations, are copied to the new store.
+    ///
+    /// Panics if one of the values was removed, or if its type was not
+    /// registered with `register_clone`.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn clone_subset(&self, tokens: &[AnyToken]) -> Store {
+        let provenance = caller_provenance();
+        let mut clone = Store::new();
+        clone.cloners = self.cloners.clone();
+        clone.comparators = self.comparators.clone();
+        for token in tokens {
+            if !self.is_alive(token) {
+                panic!("Attempted to clone a value that was already removed!");
+            }
+            let slot = &self.values[token.id];
+            let cloner = match self.cloners.get(&slot.type_id) {
+                Some(cloner) => cloner,
+                None => panic!(
+                    "Attempted to clone a value of type {} which was not registered as clonable!",
+                    slot.type_name
+                ),
+            };
+            let value = cloner(&**slot.value.as_ref().unwrap());
+            // a value listed twice is only cloned once
+            let (type_name, origin) = (slot.type_name, slot.provenance);
+            let placed = clone.place_at(token.id, token.generation, type_name, origin, value, provenance);
+            if let (Some(_), Some(stable_id)) = (placed, slot.stable_id) {
+                clone.assign_stable_id(token.id, stable_id);
+            }
+        }
+        clone
+    }
+
+    // Store a value in a given slot and generation, growing the store if needed
+    //
+    // `type_name` and `origin` describe the value, `provenance` is recorded
+    // for the vacant slots created. Returns `None` if the slot is not vacant.
+    // Panics if the slot already reached a later generation, as the tokens of
+    // its previous values would then resolve to this one.
+    pub(crate) fn place_at(
+        &mut self,
+        id: usize,
+        generation: u32,
+        type_name: &'static str,
+        origin: Provenance,
+        value: Box<dyn Any>,
+        provenance: Provenance,
+    ) -> Option<AnyToken> {
+        while self.values.len() <= id {
+            let vacant = self.push_vacant(provenance);
+            self.free.push_back(vacant);
+        }
+        if let Err(error) = self.check_any(&*value) {
+            panic!("Attempted to store an invalid value of type {}: {}", type_name, error);
+        }
+        let position = self.free.iter().rposition(|&free| free == id)?;
+        // a slot still at its initial generation never gave out a token
+        let current = self.values[id].generation;
+        if generation < current && current != self.initial_generation(id) {
+            panic!("Attempted to place a value at an older generation than its slot!");
+        }
+        self.free.remove(position);
+        self.values[id].generation = generation;
+        let type_id = (*value).type_id();
+        let token = self.allocate_any(id, type_id, type_name, origin);
+        self.occupy_any(token.clone(), value);
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clone_subset() {
+        let mut store = Store::new();
+        store.register_clone::<String>();
+        store.insert(String::from("not cloned"));
+        let token = store.insert(String::from("I like trains"));
+        store.set_stable_id(&token, 7);
+        let subset = store.clone_subset(&[AnyToken::from(&token), AnyToken::from(&token)]);
+        assert_eq!(subset.len(), 1);
+        let (index, generation) = token.clone().into_raw_parts();
+        let cloned = subset.token_from_raw_parts::<String>(index, generation).unwrap();
+        assert_eq!(subset.get(&cloned), "I like trains");
+        assert_eq!(subset.token_by_stable_id::<String>(7), Some(cloned.clone()));
+        store.get_mut(&token).clear();
+        assert_eq!(subset.get(&cloned), "I like trains");
+        assert!(subset.debug_validate().is_ok());
+    }
+
+    #[test]
+    #[should_panic]
+    fn clone_unregistered() {
+        let mut store = Store::new();
+        let token = store.insert(42);
+        store.clone_subset(&[AnyToken::from(token)]);
+    }
+}
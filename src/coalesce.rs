@@ -0,0 +1,218 @@
+use super::{AnyToken, Store, StoreEvent};
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+/// When a watcher of `coalesced_events_with` receives its events
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Coalescing {
+    /// At every call to `flush_events`
+    Flush,
+    /// At the first call to `flush_events` made once no event was recorded
+    /// for this duration
+    Quiescence(Duration),
+}
+
+// A listener of `coalesced_events`, and the events waiting for it
+struct Watcher {
+    sender: Sender<StoreEvent>,
+    coalescing: Coalescing,
+    // `None` marks an event cancelled by a later one
+    events: Vec<Option<StoreEvent>>,
+    // position in `events` of the pending event of each value
+    positions: HashMap<AnyToken, usize>,
+    last: Option<Instant>,
+}
+
+impl Watcher {
+    fn push(&mut self, event: &StoreEvent, now: Instant) {
+        self.last = Some(now);
+        let token = match *event {
+            StoreEvent::Inserted(ref token) | StoreEvent::Removed(ref token) | StoreEvent::Replaced(ref token) => {
+                token
+            }
+            StoreEvent::Bulk(count) => {
+                // all the bulk changes are merged in the first one
+                for pending in self.events.iter_mut().flatten() {
+                    if let StoreEvent::Bulk(ref mut pending) = *pending {
+                        *pending += count;
+                        return;
+                    }
+                }
+                self.events.push(Some(event.clone()));
+                return;
+            }
+        };
+        match self.positions.get(token).cloned() {
+            Some(position) => match (&self.events[position], event) {
+                // the listener has not seen the old value yet
+                (&Some(StoreEvent::Inserted(_)), &StoreEvent::Replaced(_)) => {}
+                // nor the value at all
+                (&Some(StoreEvent::Inserted(_)), &StoreEvent::Removed(_)) => {
+                    self.events[position] = None;
+                    self.positions.remove(token);
+                }
+                _ => self.events[position] = Some(event.clone()),
+            },
+            None => {
+                self.positions.insert(token.clone(), self.events.len());
+                self.events.push(Some(event.clone()));
+            }
+        }
+    }
+
+    fn is_ready(&self, now: Instant) -> bool {
+        match self.coalescing {
+            Coalescing::Flush => true,
+            Coalescing::Quiescence(quiet) => self.last.is_none_or(|last| now.duration_since(last) >= quiet),
+        }
+    }
+
+    // Sends the pending events, returns `None` if the receiver was dropped
+    fn flush(&mut self) -> Option<usize> {
+        self.positions.clear();
+        let mut sent = 0;
+        for event in ::std::mem::take(&mut self.events).into_iter().flatten() {
+            self.sender.send(event).ok()?;
+            sent += 1;
+        }
+        Some(sent)
+    }
+}
+
+// The listeners of `coalesced_events`
+#[derive(Default)]
+pub(crate) struct Coalesced {
+    watchers: Vec<Watcher>,
+}
+
+impl Coalesced {
+    fn push(&mut self, event: &StoreEvent) {
+        if self.watchers.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        for watcher in &mut self.watchers {
+            watcher.push(event, now);
+        }
+    }
+}
+
+impl Store {
+    /// Subscribe to the mutations of this store, coalesced between flushes
+    ///
+    /// Unlike `events`, nothing is sent until `flush_events` is called, for
+    /// example once per frame. Each value is then reported at most once, by
+    /// its last event: a value replaced many times produces a single
+    /// `StoreEvent::Replaced`, a value inserted then replaced a single
+    /// `StoreEvent::Inserted`, and a value inserted then removed nothing.
+    ///
+    /// The receiver can be dropped at any time to unsubscribe.
+    pub fn coalesced_events(&mut self) -> Receiver<StoreEvent> {
+        self.coalesced_events_with(Coalescing::Flush)
+    }
+
+    /// Subscribe to the coalesced mutations of this store, sent as specified
+    ///
+    /// The events are coalesced as for `coalesced_events`, but with
+    /// `Coalescing::Quiescence` they keep being merged across the calls to
+    /// `flush_events` until the store has been quiet long enough.
+    pub fn coalesced_events_with(&mut self, coalescing: Coalescing) -> Receiver<StoreEvent> {
+        let (sender, receiver) = channel();
+        self.coalesced.watchers.push(Watcher {
+            sender,
+            coalescing,
+            events: Vec::new(),
+            positions: HashMap::new(),
+            last: None,
+        });
+        receiver
+    }
+
+    /// Send the pending coalesced events to the listeners ready for them
+    ///
+    /// Returns the number of events sent, counted once per listener.
+    pub fn flush_events(&mut self) -> usize {
+        let now = Instant::now();
+        let mut sent = 0;
+        self.coalesced.watchers.retain_mut(|watcher| {
+            if !watcher.is_ready(now) {
+                return true;
+            }
+            match watcher.flush() {
+                Some(count) => {
+                    sent += count;
+                    true
+                }
+                None => false,
+            }
+        });
+        sent
+    }
+
+    // Records an event for the listeners of `coalesced_events`
+    pub(crate) fn coalesce(&mut self, event: &StoreEvent) {
+        self.coalesced.push(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coalesced_events() {
+        let mut store = Store::new();
+        let events = store.coalesced_events();
+        let kept = store.insert(1);
+        for i in 0..100 {
+            store.replace(&kept, i);
+        }
+        let removed = store.insert(2);
+        store.remove(removed);
+        assert!(events.try_recv().is_err());
+        assert_eq!(store.flush_events(), 1);
+        match events.try_recv() {
+            Ok(StoreEvent::Inserted(token)) => assert_eq!(token, AnyToken::from(&kept)),
+            _ => panic!("Expected the insertion of the first value."),
+        }
+        assert!(events.try_recv().is_err());
+        store.replace(&kept, 0);
+        let removed = store.insert(3);
+        assert_eq!(store.flush_events(), 2);
+        match events.try_recv() {
+            Ok(StoreEvent::Replaced(_)) => {}
+            _ => panic!("Expected the replacement of the first value."),
+        }
+        match events.try_recv() {
+            Ok(StoreEvent::Inserted(_)) => {}
+            _ => panic!("Expected the insertion of the third value."),
+        }
+        store.replace(&removed, 4);
+        store.remove(removed);
+        assert_eq!(store.flush_events(), 1);
+        match events.try_recv() {
+            Ok(StoreEvent::Removed(_)) => {}
+            _ => panic!("Expected the removal of the third value."),
+        }
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn coalesced_quiescence() {
+        let mut store = Store::new();
+        let every_flush = store.coalesced_events();
+        let quiet = store.coalesced_events_with(Coalescing::Quiescence(Duration::from_secs(3600)));
+        let token = store.insert(1);
+        assert_eq!(store.flush_events(), 1);
+        assert!(every_flush.try_recv().is_ok());
+        store.replace(&token, 2);
+        assert_eq!(store.flush_events(), 1);
+        assert!(quiet.try_recv().is_err());
+        let immediate = store.coalesced_events_with(Coalescing::Quiescence(Duration::from_secs(0)));
+        store.replace(&token, 3);
+        assert_eq!(store.flush_events(), 2);
+        assert!(immediate.try_recv().is_ok());
+        assert!(quiet.try_recv().is_err());
+    }
+}
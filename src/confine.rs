@@ -0,0 +1,41 @@
+use super::Store;
+#[cfg(debug_assertions)]
+use std::thread;
+
+impl Store {
+    // Panics if the store is used from another thread than the one owning it
+    //
+    // The store is not `Send`, so this can only happen if unsafe code moved
+    // it to another thread. Only checked with debug assertions.
+    #[inline]
+    pub(crate) fn check_thread(&self) {
+        #[cfg(debug_assertions)]
+        {
+            if self.owner != thread::current().id() {
+                panic!("Attempted to use a Store from another thread than the one that created it!");
+            }
+        }
+    }
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    struct ForceSend(Store);
+    unsafe impl Send for ForceSend {}
+
+    #[test]
+    fn wrong_thread() {
+        let mut store = Store::new();
+        let token = store.insert(42);
+        let moved = ForceSend(store);
+        let result = thread::spawn(move || {
+            let moved = moved;
+            let _ = moved.0.contains(&token);
+        })
+        .join();
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,120 @@
+//! A cursor over the contents of a store
+
+use super::{AnyToken, Store, StoreEvent};
+use std::any::Any;
+
+/// A cursor over the values of a store
+///
+/// Unlike iterators, a cursor can remove or replace the value it is currently
+/// pointing at while walking the store, which allows for single-pass sweeps
+/// without collecting tokens first.
+///
+/// It is created by `Store::cursor`, and visits the values in slot order.
+pub struct Cursor<'a> {
+    store: &'a mut Store,
+    next: usize,
+    current: Option<usize>,
+}
+
+impl<'a> Cursor<'a> {
+    /// Advance to the next value of the store
+    ///
+    /// Returns its token and the value, or `None` if all values were visited.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<(AnyToken, &mut dyn Any)> {
+        self.current = None;
+        while self.next < self.store.values.len() {
+            let id = self.next;
+            self.next += 1;
+            if let Some(token) = self.store.slot_token(id) {
+                self.current = Some(id);
+                self.store.mark_stale(id);
+                let slot = &mut self.store.values[id];
+                slot.version += 1;
+                let value = slot.value.as_mut().unwrap();
+                return Some((token, &mut **value));
+            }
+        }
+        None
+    }
+
+    /// Remove the current value from the store
+    ///
+    /// All its tokens are invalidated. Returns `None` if the cursor is not
+    /// pointing at a value, for example if it was already removed.
+    pub fn remove_current(&mut self) -> Option<Box<dyn Any>> {
+        let id = self.current.take()?;
+        Some(self.store.remove_at(id))
+    }
+
+    /// Replace the current value by a new one of the same type
+    ///
+    /// Returns the old value. Its tokens remain valid.
+    ///
+    /// Panics if the cursor is not pointing at a value, if the current value
+    /// is not of type `V`, or if the new value is rejected by the validator
+    /// of `V`.
+    pub fn replace_current<V: Any + 'static>(&mut self, value: V) -> V {
+        let id = match self.current {
+            Some(id) => id,
+            None => panic!("Attempted to replace the current value of a Cursor not pointing at a value!"),
+        };
+        self.store.assert_valid(&value);
+        let old = match self.store.values[id]
+            .value
+            .as_mut()
+            .and_then(|v| v.downcast_mut::<V>())
+        {
+            Some(old) => ::std::mem::replace(old, value),
+            None => panic!("Attempted to replace the current value of a Cursor with a value of another type!"),
+        };
+        self.store.mark_stale(id);
+        let token = self.store.slot_token(id).unwrap();
+        self.store.emit(StoreEvent::Replaced(token));
+        old
+    }
+}
+
+impl Store {
+    /// Create a cursor over the values of this store
+    ///
+    /// See `Cursor` for details.
+    pub fn cursor(&mut self) -> Cursor<'_> {
+        Cursor {
+            store: self,
+            next: 0,
+            current: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sweep() {
+        let mut store = Store::new();
+        let tokens = (0..6).map(|i| store.insert(i)).collect::<Vec<_>>();
+        let other = store.insert("I like trains");
+        {
+            let mut cursor = store.cursor();
+            while let Some((_, value)) = cursor.next() {
+                let number = match value.downcast_ref::<i32>() {
+                    Some(&number) => number,
+                    None => continue,
+                };
+                if number % 2 == 0 {
+                    cursor.remove_current();
+                } else {
+                    assert_eq!(cursor.replace_current(number * 10), number);
+                }
+            }
+        }
+        assert!(!tokens[0].is_valid());
+        assert_eq!(*store.get(&tokens[1]), 10);
+        assert_eq!(*store.get(&tokens[5]), 50);
+        assert_eq!(*store.get(&other), "I like trains");
+        assert_eq!(store.iter().count(), 4);
+    }
+}
@@ -0,0 +1,161 @@
+use super::{AnyToken, Store, StoreProxy, Token};
+use std::any::Any;
+
+type Command = Box<dyn FnOnce(&mut Store) -> Option<AnyToken>>;
+
+/// A queue of insertions and replacements to apply to a store later
+///
+/// This allows preparing modifications of a store while it is borrowed,
+/// for example during an iteration, and applying them all at once with
+/// `apply` when the borrow ends.
+pub struct CommandBuffer {
+    commands: Vec<Command>,
+}
+
+impl CommandBuffer {
+    /// Create a new empty command buffer
+    pub fn new() -> CommandBuffer {
+        CommandBuffer { commands: Vec::new() }
+    }
+
+    /// Queue the insertion of a value
+    ///
+    /// Its token will be returned by `apply`.
+    pub fn insert<V: Any + 'static>(&mut self, value: V) {
+        self.commands
+            .push(Box::new(move |store: &mut Store| Some(AnyToken::from(store.insert(value)))));
+    }
+
+    /// Queue the replacement of a value
+    ///
+    /// If the value was removed by the time the buffer is applied, this
+    /// replacement is skipped.
+    pub fn replace<V: Any + 'static>(&mut self, token: &Token<V>, value: V) {
+        let token = token.clone();
+        self.commands.push(Box::new(move |store: &mut Store| {
+            if store.contains(&token) {
+                store.replace(&token, value);
+            }
+            None
+        }));
+    }
+
+    /// Number of queued commands
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Check wether no command is queued
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Apply all the queued commands to a store, in the order they were queued
+    ///
+    /// Returns the tokens of the inserted values, in the order they were
+    /// queued. The buffer is left empty and can be reused.
+    pub fn apply(&mut self, store: &mut Store) -> Vec<AnyToken> {
+        self.commands
+            .drain(..)
+            .filter_map(|command| command(store))
+            .collect()
+    }
+}
+
+impl Default for CommandBuffer {
+    fn default() -> CommandBuffer {
+        CommandBuffer::new()
+    }
+}
+
+impl Store {
+    /// Schedule a value for removal
+    ///
+    /// This only needs a shared reference to the store, so it can be called
+    /// while iterating over it. The value is actually removed by the next
+    /// call to `flush_removals`, until then it remains accessible.
+    pub fn mark_for_removal(&self, token: &AnyToken) {
+        self.pending_removals.borrow_mut().push(token.clone());
+    }
+
+    /// Remove all the values scheduled by `mark_for_removal`
+    ///
+    /// The removed values are returned along with their (now invalid) tokens,
+    /// in the order they were marked. Values that were marked twice or that
+    /// were already removed are skipped, and the values with a finalizer are
+    /// given to it instead of being returned.
+    pub fn flush_removals(&mut self) -> Vec<(AnyToken, Box<dyn Any>)> {
+        let pending = ::std::mem::take(&mut *self.pending_removals.borrow_mut());
+        let mut removed = Vec::with_capacity(pending.len());
+        for token in pending {
+            if self.is_alive(&token) {
+                if let Some(value) = self.take_or_finalize(token.id) {
+                    removed.push((token, value));
+                }
+            }
+        }
+        removed
+    }
+}
+
+impl<'store> StoreProxy<'store> {
+    /// Schedule a value of the proxified store for removal
+    ///
+    /// Unlike `remove`, this is allowed for the values that are currently
+    /// borrowed. See `Store::mark_for_removal` for full documentation.
+    pub fn mark_for_removal(&self, token: &AnyToken) {
+        self.store.mark_for_removal(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deferred_removal() {
+        let mut store = Store::new();
+        store.insert_iter(0..10);
+        for (token, value) in store.iter() {
+            if value.downcast_ref::<i32>().unwrap() % 2 == 0 {
+                store.mark_for_removal(&token);
+            }
+        }
+        assert_eq!(store.len(), 10);
+        let removed = store.flush_removals();
+        assert_eq!(removed.len(), 5);
+        assert_eq!(store.values_cloned::<i32>(), vec![1, 3, 5, 7, 9]);
+        assert!(store.flush_removals().is_empty());
+    }
+
+    #[test]
+    fn command_buffer() {
+        let mut store = Store::new();
+        let token = store.insert(1);
+        let mut buffer = CommandBuffer::new();
+        for (_, value) in store.iter() {
+            let value = *value.downcast_ref::<i32>().unwrap();
+            buffer.insert(value + 1);
+            buffer.replace(&token, value * 10);
+        }
+        assert_eq!(buffer.len(), 2);
+        let tokens = buffer.apply(&mut store);
+        assert!(buffer.is_empty());
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(store.type_name_of(&tokens[0]), Some("i32"));
+        assert_eq!(*store.get(&token), 10);
+        assert_eq!(store.values_cloned::<i32>(), vec![10, 2]);
+    }
+
+    #[test]
+    fn deferred_removal_in_scope() {
+        let mut store = Store::new();
+        let token = store.insert(42);
+        store.with_value(&token, |proxy, _| {
+            proxy.mark_for_removal(&AnyToken::from(&token));
+            proxy.mark_for_removal(&AnyToken::from(&token));
+        });
+        assert_eq!(store.flush_removals().len(), 1);
+        assert!(!token.is_valid());
+    }
+}
@@ -0,0 +1,103 @@
+use super::{AnyToken, Store, Token};
+use std::any::Any;
+use std::rc::Rc;
+
+// The state of each dependency when a derived value was computed
+pub(crate) type Stamps = Vec<Option<u64>>;
+
+/// A value computed from other values of the store
+///
+/// It is stored by `Store::derive_value`, and recomputed by
+/// `Store::get_derived` when one of its dependencies was modified or
+/// removed since it was last computed.
+pub struct Derived<D> {
+    deps: Vec<AnyToken>,
+    compute: Rc<dyn Fn(&Store) -> D>,
+    cache: Option<(Stamps, D)>,
+}
+
+impl<D> Derived<D> {
+    /// Check wether the value was computed at least once
+    pub fn is_computed(&self) -> bool {
+        self.cache.is_some()
+    }
+}
+
+impl Store {
+    /// Insert a value derived from other values of this store
+    ///
+    /// `compute` is called with the store to compute the value, on first
+    /// access and whenever one of the values of `deps` was mutably accessed
+    /// or removed since the last computation. It should only read values
+    /// listed in `deps`, as changes to other values are not tracked.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn derive_value<D, F>(&mut self, deps: &[AnyToken], compute: F) -> Token<Derived<D>>
+    where
+        D: Any + 'static,
+        F: Fn(&Store) -> D + 'static,
+    {
+        self.insert(Derived {
+            deps: deps.to_vec(),
+            compute: Rc::new(compute),
+            cache: None,
+        })
+    }
+
+    /// Access a derived value, recomputing it if needed
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn get_derived<D: Any + 'static>(&mut self, token: &Token<Derived<D>>) -> &D {
+        let (stamps, compute) = {
+            let derived = self.get(token);
+            let stamps = derived.deps.iter().map(|dep| self.stamp(dep)).collect::<Stamps>();
+            match derived.cache {
+                Some((ref cached, _)) if *cached == stamps => (stamps, None),
+                _ => (stamps, Some(derived.compute.clone())),
+            }
+        };
+        if let Some(compute) = compute {
+            let value = compute(self);
+            self.get_mut(token).cache = Some((stamps, value));
+        }
+        &self.get(token).cache.as_ref().unwrap().1
+    }
+
+    // The version of a value, or `None` if it was removed
+    pub(crate) fn stamp(&self, token: &AnyToken) -> Option<u64> {
+        if self.is_alive(token) {
+            Some(self.values[token.id].version)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn derived_value() {
+        let mut store = Store::new();
+        let width = store.insert(10);
+        let height = store.insert(20);
+        let computations = Rc::new(Cell::new(0));
+        let area = {
+            let computations = computations.clone();
+            let (w, h) = (width.clone(), height.clone());
+            let deps = [AnyToken::from(&width), AnyToken::from(&height)];
+            store.derive_value(&deps, move |store| {
+                computations.set(computations.get() + 1);
+                store.get(&w) * store.get(&h)
+            })
+        };
+        assert_eq!(*store.get_derived(&area), 200);
+        assert_eq!(*store.get_derived(&area), 200);
+        assert_eq!(computations.get(), 1);
+        *store.get_mut(&width) = 30;
+        assert_eq!(*store.get_derived(&area), 600);
+        assert_eq!(computations.get(), 2);
+    }
+}
@@ -0,0 +1,107 @@
+use super::{AnyToken, Slot, Store};
+use std::any::{Any, TypeId};
+
+// Compares two boxed values whose concrete type is known to be V
+pub(crate) type EqFn = fn(&dyn Any, &dyn Any) -> bool;
+
+fn eq_values<V: Any + PartialEq + 'static>(a: &dyn Any, b: &dyn Any) -> bool {
+    a.downcast_ref::<V>() == b.downcast_ref::<V>()
+}
+
+/// A difference between two stores
+///
+/// These are produced by `Store::diff`.
+#[derive(Debug, Clone)]
+pub enum Change {
+    /// A value is only present in the second store, with this token
+    Added(AnyToken),
+    /// A value is only present in the first store, with this token
+    Removed(AnyToken),
+    /// A value is present in both stores, but was modified
+    ///
+    /// The token is the one of the second store.
+    Modified(AnyToken),
+}
+
+impl Store {
+    /// Register `V` as a comparable type
+    ///
+    /// This allows values of this type to be compared by `diff`.
+    pub fn register_eq<V: Any + PartialEq + 'static>(&mut self) {
+        self.comparators.insert(TypeId::of::<V>(), eq_values::<V>);
+    }
+
+    /// List the differences between two stores
+    ///
+    /// The values are matched by slot index and generation, so this is meant
+    /// to compare a store with a copy of itself made by `clone_subset`. The
+    /// values present in both stores are compared using the types registered
+    /// with `register_eq` in the first store. The changes are listed in slot
+    /// order.
+    ///
+    /// Panics if a value present in both stores has a type that was not
+    /// registered.
+    pub fn diff(a: &Store, b: &Store) -> Vec<Change> {
+        let mut changes = Vec::new();
+        for id in 0..::std::cmp::max(a.values.len(), b.values.len()) {
+            match (a.slot_token(id), b.slot_token(id)) {
+                (Some(a_token), Some(b_token)) => {
+                    let (a_slot, b_slot) = (&a.values[id], &b.values[id]);
+                    if a_slot.generation != b_slot.generation || a_slot.type_id != b_slot.type_id {
+                        changes.push(Change::Removed(a_token));
+                        changes.push(Change::Added(b_token));
+                    } else if !a.values_eq(a_slot, b_slot) {
+                        changes.push(Change::Modified(b_token));
+                    }
+                }
+                (Some(a_token), None) => changes.push(Change::Removed(a_token)),
+                (None, Some(b_token)) => changes.push(Change::Added(b_token)),
+                (None, None) => {}
+            }
+        }
+        changes
+    }
+
+    // Compare the values of two occupied slots holding the same type
+    fn values_eq(&self, a: &Slot, b: &Slot) -> bool {
+        let eq = match self.comparators.get(&a.type_id) {
+            Some(eq) => eq,
+            None => panic!(
+                "Attempted to compare values of type {} which was not registered as comparable!",
+                a.type_name
+            ),
+        };
+        eq(&**a.value.as_ref().unwrap(), &**b.value.as_ref().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_snapshot() {
+        let mut store = Store::new();
+        store.register_clone::<i32>();
+        store.register_eq::<i32>();
+        store.insert(1);
+        let modified = store.insert(2);
+        let removed = store.insert(3);
+        let tokens = store.iter().map(|(token, _)| token).collect::<Vec<_>>();
+        let snapshot = store.clone_subset(&tokens);
+        *store.get_mut(&modified) += 1;
+        store.remove(removed);
+        let added = store.insert(4);
+        let changes = Store::diff(&snapshot, &store);
+        assert_eq!(changes.len(), 3);
+        match (&changes[0], &changes[1], &changes[2]) {
+            (Change::Modified(m), Change::Removed(r), Change::Added(a)) => {
+                assert_eq!(*m, AnyToken::from(&modified));
+                assert_eq!(r.id, tokens[2].id);
+                assert_eq!(*a, AnyToken::from(&added));
+            }
+            _ => panic!("Unexpected changes: {:?}", changes),
+        }
+        assert!(Store::diff(&store, &store).is_empty());
+    }
+}
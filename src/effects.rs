@@ -0,0 +1,92 @@
+use super::{AnyToken, Store};
+use derived::Stamps;
+use std::mem;
+
+// An effect registered with `add_effect`
+pub(crate) struct Effect {
+    deps: Vec<AnyToken>,
+    stamps: Stamps,
+    run: Box<dyn FnMut(&mut Store)>,
+}
+
+impl Store {
+    // Makes the effects depending on a revoked token depend on its successor
+    pub(crate) fn retarget_effects(&mut self, old: &AnyToken, new: &AnyToken) {
+        for dep in self.effects.iter_mut().flat_map(|effect| effect.deps.iter_mut()) {
+            if dep == old {
+                *dep = new.clone();
+            }
+        }
+    }
+
+    /// Register an effect to run when some values change
+    ///
+    /// The effect does not run immediately: `run_effects` runs it if one of
+    /// the values of `deps` was mutably accessed or removed since it last
+    /// ran, or since it was registered. It is dropped once all the values of
+    /// `deps` have been removed.
+    pub fn add_effect<F: FnMut(&mut Store) + 'static>(&mut self, deps: &[AnyToken], f: F) {
+        let stamps = deps.iter().map(|dep| self.stamp(dep)).collect();
+        self.effects.push(Effect {
+            deps: deps.to_vec(),
+            stamps,
+            run: Box::new(f),
+        });
+    }
+
+    /// Run the effects whose dependencies changed
+    ///
+    /// Each effect runs at most once per call, however many changes its
+    /// dependencies went through, in the order the effects were registered.
+    /// Changes made by the effects themselves are picked up by the next call,
+    /// except for changes an effect makes to its own dependencies.
+    ///
+    /// Returns the number of effects that ran.
+    pub fn run_effects(&mut self) -> usize {
+        let mut effects = mem::take(&mut self.effects);
+        let pending = effects
+            .iter()
+            .map(|effect| effect.deps.iter().map(|dep| self.stamp(dep)).collect::<Stamps>() != effect.stamps)
+            .collect::<Vec<_>>();
+        let mut ran = 0;
+        for (effect, pending) in effects.iter_mut().zip(pending) {
+            if pending {
+                (effect.run)(self);
+                effect.stamps = effect.deps.iter().map(|dep| self.stamp(dep)).collect();
+                ran += 1;
+            }
+        }
+        effects.retain(|effect| effect.stamps.iter().any(Option::is_some));
+        // effects registered while running come after the previous ones
+        effects.append(&mut self.effects);
+        self.effects = effects;
+        ran
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn effects() {
+        let mut store = Store::new();
+        let width = store.insert(10);
+        let runs = Rc::new(Cell::new(0));
+        {
+            let runs = runs.clone();
+            store.add_effect(&[AnyToken::from(&width)], move |_| runs.set(runs.get() + 1));
+        }
+        assert_eq!(store.run_effects(), 0);
+        *store.get_mut(&width) = 20;
+        *store.get_mut(&width) = 30;
+        assert_eq!(store.run_effects(), 1);
+        assert_eq!(store.run_effects(), 0);
+        store.remove(width);
+        assert_eq!(store.run_effects(), 1);
+        assert_eq!(runs.get(), 2);
+        assert!(store.effects.is_empty());
+    }
+}
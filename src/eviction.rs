@@ -0,0 +1,130 @@
+use super::{AnyToken, Key, Store};
+use std::any::Any;
+
+impl Store {
+    /// Set the eviction priority of a value
+    ///
+    /// Values with the lowest priority are the first to be evicted by
+    /// `evict_lowest` or when the eviction limit is reached. Values are
+    /// inserted with a priority of 0.
+    ///
+    /// Panics if the value was removed from this store.
+    pub fn set_priority<K: Key>(&mut self, key: &K, priority: i32) {
+        let token = key.any_token();
+        if !self.is_alive(&token) {
+            panic!("Attempted to set the priority of a value that was already removed!");
+        }
+        self.values[token.id].priority = priority;
+    }
+
+    /// The eviction priority of a value
+    ///
+    /// Returns `None` if the value was removed from this store.
+    pub fn priority<K: Key>(&self, key: &K) -> Option<i32> {
+        let token = key.any_token();
+        if self.is_alive(&token) {
+            Some(self.values[token.id].priority)
+        } else {
+            None
+        }
+    }
+
+    /// Evict the `n` values with the lowest priority
+    ///
+    /// Among values of equal priority, the oldest ones are evicted first. The
+    /// evicted values are removed from the store and returned along with their
+    /// (now invalid) tokens. Their finalizers are discarded.
+    pub fn evict_lowest(&mut self, n: usize) -> Vec<(AnyToken, Box<dyn Any>)> {
+        self.lowest_sparing(n, &[])
+            .into_iter()
+            .map(|id| {
+                let token = self.slot_token(id).unwrap();
+                (token, self.remove_at(id))
+            })
+            .collect()
+    }
+
+    /// Set a limit on the number of values of this store
+    ///
+    /// Once the limit is reached, inserting a new value first evicts the value
+    /// with the lowest priority, as `evict_lowest` would. Evicted values are
+    /// given to their finalizer if they have one, and dropped otherwise.
+    /// Values currently borrowed by a `with_value` scope are never
+    /// evicted.
+    ///
+    /// Setting this limit does not evict anything by itself.
+    pub fn set_eviction_limit(&mut self, limit: Option<usize>) {
+        self.eviction_limit = limit;
+    }
+
+    // The slots of the `n` values with the lowest priority, lowest first
+    pub(crate) fn lowest_sparing(&self, n: usize, spared: &[usize]) -> Vec<usize> {
+        let mut candidates = Vec::with_capacity(self.len);
+        for id in 0..self.values.len() {
+            let slot = &self.values[id];
+            if slot.live.is_some() && slot.value.is_some() && !spared.contains(&id) {
+                candidates.push((slot.priority, slot.serial, id));
+            }
+        }
+        // only the `n` lowest are sorted, so that evicting one value to make
+        // room for an insertion stays linear
+        if n < candidates.len() {
+            candidates.select_nth_unstable(n);
+            candidates.truncate(n);
+        }
+        candidates.sort_unstable();
+        candidates.into_iter().map(|(_, _, id)| id).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evict_lowest() {
+        let mut store = Store::new();
+        let background = store.insert("background");
+        let ui = store.insert("ui");
+        let other = store.insert("other");
+        store.set_priority(&ui, 10);
+        store.set_priority(&background, -10);
+        assert_eq!(store.priority(&ui), Some(10));
+        let evicted = store.evict_lowest(2);
+        assert_eq!(evicted.len(), 2);
+        assert!(!background.is_valid());
+        assert!(!other.is_valid());
+        assert!(ui.is_valid());
+        assert_eq!(store.priority(&background), None);
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn eviction_limit() {
+        let mut store = Store::new();
+        store.set_eviction_limit(Some(2));
+        let first = store.insert(1);
+        let second = store.insert(2);
+        store.set_priority(&first, 1);
+        let third = store.insert(3);
+        assert_eq!(store.len(), 2);
+        assert!(first.is_valid());
+        assert!(!second.is_valid());
+        assert!(third.is_valid());
+    }
+
+    #[test]
+    fn no_eviction_of_borrowed() {
+        let mut store = Store::new();
+        store.set_eviction_limit(Some(1));
+        let first = store.insert(1);
+        let second = store.with_value(&first, |proxy, value| {
+            let second = proxy.insert(2);
+            *value += 1;
+            second
+        });
+        assert_eq!(*store.get(&first), 2);
+        assert!(second.is_valid());
+        assert_eq!(store.len(), 2);
+    }
+}
@@ -0,0 +1,110 @@
+use super::{caller_provenance, AnyToken, Store};
+use std::any::Any;
+
+impl Store {
+    /// Move all the values out of this store, along with their tokens
+    ///
+    /// The values are returned in slot order. Their tokens are no longer
+    /// valid, but they keep the slot index and generation of the values, so
+    /// that `import` can put them back in the same places.
+    pub fn export(&mut self) -> Vec<(AnyToken, Box<dyn Any>)> {
+        let mut exported = Vec::with_capacity(self.len);
+        for id in 0..self.values.len() {
+            if let Some(token) = self.slot_token(id) {
+                exported.push((token, self.remove_at(id)));
+            }
+        }
+        exported
+    }
+
+    /// Move values into this store, in the slots given by their tokens
+    ///
+    /// Each value is stored with the slot index and generation of its token,
+    /// as returned by `export`, so the raw parts and handles of the original
+    /// tokens can be resolved in this store. The new tokens are returned in
+    /// the same order as the values.
+    ///
+    /// The store does not know the names of the imported types, so they are
+    /// missing from diagnostics and from `manifest`.
+    ///
+    /// Panics if this store is sealed, if one of these slots is not vacant in
+    /// this store or was used by this store at a later generation, or if a
+    /// value is rejected by the validator of its type.
+    pub fn import(&mut self, values: Vec<(AnyToken, Box<dyn Any>)>) -> Vec<AnyToken> {
+        if self.sealed {
+            panic!("Attempted to import values in a sealed Store!");
+        }
+        let provenance = caller_provenance();
+        let mut tokens = Vec::with_capacity(values.len());
+        for (token, value) in values {
+            match self.place_at(token.id, token.generation, "<imported>", token.provenance, value, provenance) {
+                Some(token) => tokens.push(token),
+                None => panic!("Attempted to import a value into an occupied slot!"),
+            }
+        }
+        tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_import() {
+        let mut store = Store::new();
+        store.insert(1);
+        let token = store.insert(String::from("I like trains"));
+        let (index, generation) = token.clone().into_raw_parts();
+        let exported = store.export();
+        assert!(store.is_empty());
+        assert!(!token.is_valid());
+        let mut other = Store::new();
+        other.insert(2);
+        let tokens = other.import(exported.into_iter().skip(1).collect());
+        assert_eq!(tokens.len(), 1);
+        let token = other.token_from_raw_parts::<String>(index, generation).unwrap();
+        assert_eq!(other.get(&token), "I like trains");
+        assert!(other.debug_validate().is_ok());
+    }
+
+    #[test]
+    fn import_after_removal() {
+        let mut store = Store::new();
+        store.set_generation_salting(false);
+        let stale = store.insert(1);
+        store.remove(stale.clone());
+        let mut source = Store::new();
+        source.set_generation_salting(false);
+        let removed = source.insert(2);
+        source.remove(removed);
+        source.insert(99);
+        store.import(source.export());
+        assert!(!store.contains(&stale));
+        assert!(store.try_get(&stale).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempted to place a value at an older generation than its slot!")]
+    fn import_older_generation() {
+        let mut store = Store::new();
+        store.set_generation_salting(false);
+        let stale = store.insert(1);
+        store.remove(stale);
+        let mut source = Store::new();
+        source.set_generation_salting(false);
+        source.insert(99);
+        store.import(source.export());
+    }
+
+    #[test]
+    #[should_panic]
+    fn import_occupied() {
+        let mut store = Store::new();
+        store.insert(1);
+        let exported = store.export();
+        let mut other = Store::new();
+        other.insert(2);
+        other.import(exported);
+    }
+}
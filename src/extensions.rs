@@ -0,0 +1,127 @@
+use super::{AnyToken, Store, Token};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+/// A type map backed by a store
+///
+/// This holds at most one value of each type, with the same semantics as
+/// the extensions maps of `http` or `actix`: values are inserted, accessed
+/// and removed by their type alone.
+///
+/// Each value still lives in a `Store`, so code can progressively switch to
+/// tokens using `token`, and eventually take the store with `into_store`.
+pub struct Extensions {
+    store: Store,
+    tokens: HashMap<TypeId, AnyToken>,
+}
+
+impl Extensions {
+    /// Create a new empty type map
+    pub fn new() -> Extensions {
+        Extensions {
+            store: Store::new(),
+            tokens: HashMap::new(),
+        }
+    }
+
+    /// Insert a value in this map
+    ///
+    /// If a value of this type was already present, it is replaced and
+    /// returned, and its tokens remain valid.
+    pub fn insert<T: Any + 'static>(&mut self, value: T) -> Option<T> {
+        match self.token::<T>() {
+            Some(token) => Some(self.store.replace(&token, value)),
+            None => {
+                let token = self.store.insert(value);
+                self.tokens.insert(TypeId::of::<T>(), AnyToken::from(token));
+                None
+            }
+        }
+    }
+
+    /// Access the value of type `T`, if any
+    pub fn get<T: Any + 'static>(&self) -> Option<&T> {
+        self.token::<T>().map(|token| self.store.get(&token))
+    }
+
+    /// Mutably access the value of type `T`, if any
+    pub fn get_mut<T: Any + 'static>(&mut self) -> Option<&mut T> {
+        match self.token::<T>() {
+            Some(token) => Some(self.store.get_mut(&token)),
+            None => None,
+        }
+    }
+
+    /// Remove the value of type `T` from this map, if any
+    pub fn remove<T: Any + 'static>(&mut self) -> Option<T> {
+        let token = self.token::<T>()?;
+        self.tokens.remove(&TypeId::of::<T>());
+        Some(self.store.remove(token))
+    }
+
+    /// Check wether this map contains a value of type `T`
+    pub fn contains<T: Any + 'static>(&self) -> bool {
+        self.token::<T>().is_some()
+    }
+
+    /// The token of the value of type `T`, if any
+    pub fn token<T: Any + 'static>(&self) -> Option<Token<T>> {
+        let token = self.tokens.get(&TypeId::of::<T>())?;
+        self.store.token_from_raw_parts(token.id as u32, token.generation)
+    }
+
+    /// Number of values in this map
+    pub fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    /// Check wether this map contains no values
+    pub fn is_empty(&self) -> bool {
+        self.store.is_empty()
+    }
+
+    /// Remove all the values of this map
+    pub fn clear(&mut self) {
+        *self = Extensions::new();
+    }
+
+    /// Access the store backing this map
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Consume this map, recovering the store backing it
+    pub fn into_store(self) -> Store {
+        self.store
+    }
+}
+
+impl Default for Extensions {
+    fn default() -> Extensions {
+        Extensions::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_map() {
+        let mut ext = Extensions::new();
+        assert_eq!(ext.insert(5i32), None);
+        assert_eq!(ext.insert("I like trains"), None);
+        assert_eq!(ext.insert(6i32), Some(5));
+        assert_eq!(ext.get::<i32>(), Some(&6));
+        *ext.get_mut::<&str>().unwrap() = "I like boats";
+        assert_eq!(ext.len(), 2);
+        let token = ext.token::<&str>().unwrap();
+        assert_eq!(*ext.store().get(&token), "I like boats");
+        assert_eq!(ext.remove::<&str>(), Some("I like boats"));
+        assert!(!token.is_valid());
+        assert!(!ext.contains::<&str>());
+        assert_eq!(ext.remove::<u8>(), None);
+        let store = ext.into_store();
+        assert_eq!(store.len(), 1);
+    }
+}
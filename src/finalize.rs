@@ -0,0 +1,157 @@
+use super::{Store, StoreProxy, Token};
+use std::any::Any;
+use std::cmp::Reverse;
+
+// A finalizer, taking the value of its slot once it was removed
+pub(crate) type Finalizer = Box<dyn FnOnce(&mut StoreProxy, Box<dyn Any>)>;
+
+impl Store {
+    /// Insert a new value in this store, along with a finalizer
+    ///
+    /// The finalizer is given the value when it is removed with `dispose`, or
+    /// by the store itself: when it is evicted, pruned, purged, removed in
+    /// bulk by `remove_all_of_type` or `flush_removals`, or when the store is
+    /// dropped. It also receives a `StoreProxy` to the store, to clean up the
+    /// other values related to this one.
+    ///
+    /// The methods that remove a given value and give it back, like `remove`,
+    /// discard its finalizer without running it.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn insert_finalized<V, F>(&mut self, value: V, finalizer: F) -> Token<V>
+    where
+        V: Any + 'static,
+        F: FnOnce(&mut StoreProxy, V) + 'static,
+    {
+        let token = self.insert(value);
+        self.values[token.id].finalizer = Some(Box::new(move |proxy: &mut StoreProxy, value: Box<dyn Any>| {
+            finalizer(proxy, *value.downcast().unwrap())
+        }));
+        token
+    }
+
+    /// Remove a value from this store, running its finalizer
+    ///
+    /// If the value has no finalizer, it is simply dropped.
+    ///
+    /// Panics if the provided token corresponds to a value that was already
+    /// removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn dispose<V: Any + 'static>(&mut self, token: Token<V>) {
+        if !self.contains(&token) {
+            self.invalid_access(&token);
+        }
+        let id = token.id;
+        drop(token);
+        self.finalize_at(id);
+    }
+
+    // Removes the value of an occupied slot, running its finalizer if any
+    pub(crate) fn finalize_at(&mut self, id: usize) {
+        self.finalize_slot(id, true);
+    }
+
+    // Like `finalize_at`, only reporting the removal to the listeners if
+    // `notify`
+    pub(crate) fn finalize_slot(&mut self, id: usize, notify: bool) {
+        let finalizer = self.values[id].finalizer.take();
+        let value = self.remove_slot(id, notify);
+        if let Some(finalizer) = finalizer {
+            finalizer(&mut self.as_proxy(), value);
+        }
+    }
+
+    // Removes the value of an occupied slot, giving it back unless it has a
+    // finalizer, which is run instead
+    pub(crate) fn take_or_finalize(&mut self, id: usize) -> Option<Box<dyn Any>> {
+        if self.values[id].finalizer.is_some() {
+            self.finalize_at(id);
+            None
+        } else {
+            Some(self.remove_at(id))
+        }
+    }
+
+    // Runs the finalizers of the current values in reverse insertion order
+    //
+    // The values inserted by these finalizers keep their own finalizers, so
+    // that a finalizer inserting a finalized value cannot loop forever.
+    pub(crate) fn run_finalizers(&mut self) {
+        let mut finalized = (0..self.values.len())
+            .filter(|&id| self.values[id].finalizer.is_some())
+            .map(|id| (id, self.values[id].serial))
+            .collect::<Vec<_>>();
+        finalized.sort_by_key(|&(_, serial)| Reverse(serial));
+        for (id, serial) in finalized {
+            let slot = &self.values[id];
+            if slot.finalizer.is_some() && slot.serial == serial {
+                self.finalize_at(id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn finalizers() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut store = Store::new();
+        let counter = store.insert(0);
+        let finalized = {
+            let (log, counter) = (log.clone(), counter.clone());
+            store.insert_finalized(1, move |proxy, value| {
+                *proxy.get_mut(&counter) += 1;
+                log.borrow_mut().push(value);
+            })
+        };
+        store.dispose(finalized);
+        assert_eq!(*log.borrow(), vec![1]);
+        assert_eq!(*store.get(&counter), 1);
+        // removing the value discards the finalizer
+        let removed = {
+            let log = log.clone();
+            store.insert_finalized(2, move |_, value| log.borrow_mut().push(value))
+        };
+        assert_eq!(store.remove(removed), 2);
+        for i in 3..5 {
+            let log = log.clone();
+            store.insert_finalized(i, move |_, value| log.borrow_mut().push(value));
+        }
+        drop(store);
+        assert_eq!(*log.borrow(), vec![1, 4, 3]);
+    }
+
+    #[test]
+    fn finalized_by_store() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut store = Store::new();
+        store.set_eviction_limit(Some(2));
+        for i in 0..3 {
+            let log = log.clone();
+            store.insert_finalized(i, move |_, value| log.borrow_mut().push(value));
+        }
+        assert_eq!(*log.borrow(), vec![0]);
+        store.insert(3);
+        assert_eq!(store.remove_all_of_type::<i32>(), vec![3]);
+        assert_eq!(*log.borrow(), vec![0, 1, 2]);
+    }
+
+    // Inserts a value whose finalizer inserts another one, forever
+    fn insert_forever(store: &mut Store, count: Rc<RefCell<usize>>) {
+        *count.borrow_mut() += 1;
+        store.insert_finalized(0, move |proxy, _| insert_forever(proxy.store, count));
+    }
+
+    #[test]
+    fn finalizers_inserting_finalizers() {
+        let count = Rc::new(RefCell::new(0));
+        let mut store = Store::new();
+        insert_forever(&mut store, count.clone());
+        drop(store);
+        assert_eq!(*count.borrow(), 2);
+    }
+}
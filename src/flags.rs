@@ -0,0 +1,81 @@
+use super::{AnyToken, Key, Store};
+
+impl Store {
+    /// Set some user flags of a value
+    ///
+    /// Every value has 32 flag bits for cheap annotations, which are all
+    /// cleared when it is inserted. The bits set in `mask` are set.
+    ///
+    /// Panics if the value was removed from this store.
+    pub fn set_flags<K: Key>(&mut self, key: &K, mask: u32) {
+        let token = key.any_token();
+        if !self.is_alive(&token) {
+            panic!("Attempted to set the flags of a value that was already removed!");
+        }
+        self.values[token.id].flags |= mask;
+    }
+
+    /// Clear some user flags of a value
+    ///
+    /// The bits set in `mask` are cleared.
+    ///
+    /// Panics if the value was removed from this store.
+    pub fn clear_flags<K: Key>(&mut self, key: &K, mask: u32) {
+        let token = key.any_token();
+        if !self.is_alive(&token) {
+            panic!("Attempted to clear the flags of a value that was already removed!");
+        }
+        self.values[token.id].flags &= !mask;
+    }
+
+    /// The user flags of a value
+    ///
+    /// Returns `None` if the value was removed from this store.
+    pub fn flags<K: Key>(&self, key: &K) -> Option<u32> {
+        let token = key.any_token();
+        if self.is_alive(&token) {
+            Some(self.values[token.id].flags)
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over the values which have all the flags of `mask` set
+    ///
+    /// Their tokens are yielded in slot order.
+    pub fn iter_flagged(&self, mask: u32) -> impl Iterator<Item = AnyToken> + '_ {
+        (0..self.values.len()).filter_map(move |id| {
+            if self.values[id].flags & mask == mask {
+                self.slot_token(id)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEEDS_REDRAW: u32 = 1;
+    const PENDING_DESTROY: u32 = 2;
+
+    #[test]
+    fn flags() {
+        let mut store = Store::new();
+        let first = store.insert(1);
+        let second = store.insert(2);
+        store.set_flags(&first, NEEDS_REDRAW | PENDING_DESTROY);
+        store.set_flags(&second, NEEDS_REDRAW);
+        store.clear_flags(&first, NEEDS_REDRAW);
+        assert_eq!(store.flags(&first), Some(PENDING_DESTROY));
+        let redraw = store.iter_flagged(NEEDS_REDRAW).collect::<Vec<_>>();
+        assert_eq!(redraw, vec![AnyToken::from(&second)]);
+        store.remove(first.clone());
+        assert_eq!(store.flags(&first), None);
+        let third = store.insert(3);
+        assert_eq!(store.flags(&third), Some(0));
+        assert_eq!(store.iter_flagged(PENDING_DESTROY).count(), 0);
+    }
+}
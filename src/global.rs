@@ -0,0 +1,193 @@
+//! A thread-local store, for programs that do not want to pass a store around
+//!
+//! Each thread has its own global store, created empty on first use. A token
+//! obtained from the global store must be used on the thread that created it,
+//! even if it was sent to another thread in between. The global store only
+//! hands out `GlobalToken`s, which check this on every access, so that a value
+//! of a thread can never be reached through the global store of another.
+
+use super::{Store, Token};
+use std::any::Any;
+use std::cell::RefCell;
+use std::thread::{self, ThreadId};
+
+thread_local! {
+    static STORE: RefCell<Store> = RefCell::new(Store::new());
+}
+
+/// The global store of a thread, as seen from `with`
+pub struct GlobalStore<'a> {
+    store: &'a mut Store,
+}
+
+/// Access the global store of the current thread
+///
+/// Panics if called from within another call to `with`, as the global store
+/// is then already borrowed.
+pub fn with<T, F: FnOnce(&mut GlobalStore) -> T>(f: F) -> T {
+    STORE.with(|store| match store.try_borrow_mut() {
+        Ok(mut store) => f(&mut GlobalStore { store: &mut store }),
+        Err(_) => panic!("Attempted to access the global Store while it was already borrowed!"),
+    })
+}
+
+impl<'a> GlobalStore<'a> {
+    /// Insert a new value in this store
+    pub fn insert<V: Any + 'static>(&mut self, value: V) -> GlobalToken<V> {
+        GlobalToken {
+            token: self.store.insert(value),
+            owner: thread::current().id(),
+        }
+    }
+
+    /// Access a value of this store
+    ///
+    /// Panics if the token was created on another thread, or if the value
+    /// was removed.
+    pub fn get<V: Any + 'static>(&self, token: &GlobalToken<V>) -> &V {
+        token.check_thread();
+        self.store.get(&token.token)
+    }
+
+    /// Mutably access a value of this store
+    ///
+    /// Panics in the same cases as `GlobalStore::get`.
+    pub fn get_mut<V: Any + 'static>(&mut self, token: &GlobalToken<V>) -> &mut V {
+        token.check_thread();
+        self.store.get_mut(&token.token)
+    }
+
+    /// Remove a value from this store
+    ///
+    /// Panics in the same cases as `GlobalStore::get`.
+    pub fn remove<V: Any + 'static>(&mut self, token: GlobalToken<V>) -> V {
+        token.check_thread();
+        self.store.remove(token.token)
+    }
+
+    /// Check wether this store contains the value of a token
+    ///
+    /// Returns `false` if the token was created on another thread.
+    pub fn contains<V: Any + 'static>(&self, token: &GlobalToken<V>) -> bool {
+        token.is_local() && self.store.contains(&token.token)
+    }
+
+    /// The number of values in this store
+    pub fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    /// Check wether this store is empty
+    pub fn is_empty(&self) -> bool {
+        self.store.is_empty()
+    }
+}
+
+/// A token to a value of the global store of a thread
+///
+/// It is created using `insert`, and panics if it is used on another thread
+/// than the one that created it.
+pub struct GlobalToken<V> {
+    token: Token<V>,
+    owner: ThreadId,
+}
+
+/// Insert a value in the global store of the current thread
+pub fn insert<V: Any + 'static>(value: V) -> GlobalToken<V> {
+    with(|store| store.insert(value))
+}
+
+impl<V: Any + 'static> GlobalToken<V> {
+    /// Access the value of this token
+    ///
+    /// Panics if called on another thread than the one that created this
+    /// token, from within a call to `with`, or if the value was removed.
+    pub fn with<T, F: FnOnce(&V) -> T>(&self, f: F) -> T {
+        with(|store| f(store.get(self)))
+    }
+
+    /// Mutably access the value of this token
+    ///
+    /// Panics in the same cases as `GlobalToken::with`.
+    pub fn with_mut<T, F: FnOnce(&mut V) -> T>(&self, f: F) -> T {
+        with(|store| f(store.get_mut(self)))
+    }
+
+    /// Remove the value of this token from the global store
+    ///
+    /// Panics in the same cases as `GlobalToken::with`.
+    pub fn remove(self) -> V {
+        with(|store| store.remove(self))
+    }
+}
+
+impl<V> GlobalToken<V> {
+    /// Check wether this token is still valid
+    pub fn is_valid(&self) -> bool {
+        self.token.is_valid()
+    }
+
+    /// Check wether this token can be used on the current thread
+    pub fn is_local(&self) -> bool {
+        self.owner == thread::current().id()
+    }
+
+    fn check_thread(&self) {
+        if !self.is_local() {
+            panic!("Attempted to use a global token on another thread than the one that created it!");
+        }
+    }
+}
+
+impl<V> Clone for GlobalToken<V> {
+    fn clone(&self) -> GlobalToken<V> {
+        GlobalToken {
+            token: self.token.clone(),
+            owner: self.owner,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn global_store() {
+        let token = with(|store| store.insert(42));
+        assert_eq!(with(|store| *store.get(&token)), 42);
+        let sent = token.clone();
+        thread::spawn(move || {
+            with(|store| {
+                store.insert(0);
+                assert_eq!(store.len(), 1);
+                assert!(!store.contains(&sent));
+            })
+        })
+        .join()
+        .unwrap();
+        with(|store| store.remove(token));
+    }
+
+    #[test]
+    fn global_token() {
+        let token = insert(1);
+        token.with_mut(|value| *value += 1);
+        assert_eq!(token.with(|value| *value), 2);
+        let sent = token.clone();
+        let result = thread::spawn(move || {
+            assert!(!sent.is_local());
+            sent.with(|value| *value)
+        })
+        .join();
+        assert!(result.is_err());
+        assert_eq!(token.remove(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn no_reentrancy() {
+        with(|_| with(|_| ()));
+    }
+}
@@ -0,0 +1,194 @@
+use super::{BoundToken, Store, StoreProxy, Token};
+use std::any::Any;
+use std::cell::{BorrowError, BorrowMutError, Ref, RefCell, RefMut};
+use std::rc::Rc;
+
+/// A shared handle to a store
+///
+/// This is a cheaply clonable handle to a `Store` with interior mutability,
+/// for code that needs the store to be reachable from many places, like
+/// callbacks of an event loop.
+///
+/// The store is borrowed dynamically for the duration of each operation, and
+/// the methods panic with an explicit message if the store is already borrowed
+/// in an incompatible way. The `try_*` variants report this as an error instead.
+pub struct StoreHandle {
+    pub(crate) inner: Rc<RefCell<Store>>,
+}
+
+impl StoreHandle {
+    /// Create a handle to a new empty store
+    pub fn new() -> StoreHandle {
+        StoreHandle::from(Store::new())
+    }
+
+    /// Insert a new value in the store
+    ///
+    /// Panics if the store is currently borrowed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn insert<V: Any + 'static>(&self, value: V) -> Token<V> {
+        self.borrow_mut().insert(value)
+    }
+
+    /// Insert a new value in the store, and bind its token to this store
+    ///
+    /// Panics if the store is currently borrowed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn insert_bound<V: Any + 'static>(&self, value: V) -> BoundToken<V> {
+        self.bind(self.insert(value))
+    }
+
+    /// Bind a token to this store
+    pub fn bind<V>(&self, token: Token<V>) -> BoundToken<V> {
+        token.bind(&self.inner)
+    }
+
+    /// Access a value of the store
+    ///
+    /// Panics if the store is currently mutably borrowed, or if the value
+    /// was removed.
+    pub fn get<V: Any + 'static>(&self, token: &Token<V>) -> Ref<'_, V> {
+        Ref::map(self.borrow(), |store| store.get(token))
+    }
+
+    /// Try to access a value of the store
+    ///
+    /// Returns an error if the store is currently mutably borrowed. Panics if
+    /// the value was removed.
+    pub fn try_get<V: Any + 'static>(&self, token: &Token<V>) -> Result<Ref<'_, V>, BorrowError> {
+        self.inner
+            .try_borrow()
+            .map(|store| Ref::map(store, |store| store.get(token)))
+    }
+
+    /// Mutably access a value of the store
+    ///
+    /// Panics if the store is currently borrowed, or if the value was removed.
+    pub fn get_mut<V: Any + 'static>(&self, token: &Token<V>) -> RefMut<'_, V> {
+        RefMut::map(self.borrow_mut(), |store| store.get_mut(token))
+    }
+
+    /// Try to mutably access a value of the store
+    ///
+    /// Returns an error if the store is currently borrowed. Panics if the
+    /// value was removed.
+    pub fn try_get_mut<V: Any + 'static>(&self, token: &Token<V>) -> Result<RefMut<'_, V>, BorrowMutError> {
+        self.inner
+            .try_borrow_mut()
+            .map(|store| RefMut::map(store, |store| store.get_mut(token)))
+    }
+
+    /// Remove a value from the store
+    ///
+    /// Panics if the store is currently borrowed, or if the value was
+    /// already removed.
+    pub fn remove<V: Any + 'static>(&self, token: Token<V>) -> V {
+        self.borrow_mut().remove(token)
+    }
+
+    /// Create a sub-scope with access to a value
+    ///
+    /// The store stays mutably borrowed for the whole duration of the closure.
+    /// Panics if the store is currently borrowed.
+    ///
+    /// See `Store::with_value` for full documentation.
+    pub fn with_value<V: Any + 'static, T, F>(&self, token: &Token<V>, f: F) -> T
+    where
+        F: FnOnce(&mut StoreProxy, &mut V) -> T,
+    {
+        self.borrow_mut().with_value(token, f)
+    }
+
+    /// Borrow the underlying store
+    ///
+    /// Panics if the store is currently mutably borrowed.
+    pub fn borrow(&self) -> Ref<'_, Store> {
+        match self.inner.try_borrow() {
+            Ok(store) => store,
+            Err(_) => panic!("Attempted to access the Store while it was mutably borrowed!"),
+        }
+    }
+
+    /// Mutably borrow the underlying store
+    ///
+    /// Panics if the store is currently borrowed.
+    pub fn borrow_mut(&self) -> RefMut<'_, Store> {
+        match self.inner.try_borrow_mut() {
+            Ok(store) => store,
+            Err(_) => panic!("Attempted to mutably access the Store while it was already borrowed!"),
+        }
+    }
+
+    /// Try to borrow the underlying store
+    pub fn try_borrow(&self) -> Result<Ref<'_, Store>, BorrowError> {
+        self.inner.try_borrow()
+    }
+
+    /// Try to mutably borrow the underlying store
+    pub fn try_borrow_mut(&self) -> Result<RefMut<'_, Store>, BorrowMutError> {
+        self.inner.try_borrow_mut()
+    }
+}
+
+impl Default for StoreHandle {
+    fn default() -> StoreHandle {
+        StoreHandle::new()
+    }
+}
+
+impl Clone for StoreHandle {
+    fn clone(&self) -> StoreHandle {
+        StoreHandle {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl ::std::convert::From<Store> for StoreHandle {
+    fn from(store: Store) -> StoreHandle {
+        StoreHandle {
+            inner: Rc::new(RefCell::new(store)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forwarding() {
+        let handle = StoreHandle::new();
+        let token = handle.insert(42);
+        *handle.get_mut(&token) += 1;
+        assert_eq!(*handle.get(&token), 43);
+        let other = handle.clone();
+        let bound = other.insert_bound(String::from("I like trains"));
+        handle.with_value(&token, |proxy, value| {
+            *value += proxy.get(bound.token()).len() as i32;
+        });
+        assert_eq!(*handle.get(&token), 56);
+        assert_eq!(bound.with(|v| v.len()), 13);
+        assert_eq!(handle.remove(token), 56);
+    }
+
+    #[test]
+    fn borrow_errors() {
+        let handle = StoreHandle::new();
+        let token = handle.insert(42);
+        let value = handle.get(&token);
+        assert!(handle.try_get(&token).is_ok());
+        assert!(handle.try_get_mut(&token).is_err());
+        drop(value);
+        assert!(handle.try_get_mut(&token).is_ok());
+    }
+
+    #[test]
+    #[should_panic]
+    fn no_reentrant_mut_borrow() {
+        let handle = StoreHandle::new();
+        let token = handle.insert(42);
+        let _value = handle.get(&token);
+        handle.insert(12);
+    }
+}
@@ -0,0 +1,115 @@
+use super::{Store, Token};
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+
+// The history of the slots for which it was enabled, with its maximum depth
+//
+// Previous values are stored most recent first.
+pub(crate) type Histories = HashMap<usize, (usize, VecDeque<Box<dyn Any>>)>;
+
+impl Store {
+    /// Keep the previous values of a value, up to `depth` of them
+    ///
+    /// The values replaced with `replace_recorded` are then kept in its
+    /// history, from which `rollback` can restore them. The history is
+    /// dropped along with the value. A depth of 0 disables the history.
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn enable_history<V: Any + 'static>(&mut self, token: &Token<V>, depth: usize) {
+        if !self.contains(token) {
+            self.invalid_access(token);
+        }
+        if depth == 0 {
+            self.histories.remove(&token.id);
+            return;
+        }
+        let history = self.histories.entry(token.id).or_insert_with(|| (depth, VecDeque::new()));
+        history.0 = depth;
+        history.1.truncate(depth);
+    }
+
+    /// Replace a value, keeping the old one in its history
+    ///
+    /// If the history of this value is not enabled, the old value is dropped.
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn replace_recorded<V: Any + 'static>(&mut self, token: &Token<V>, value: V) {
+        let old = self.replace(token, value);
+        if let Some(&mut (depth, ref mut previous)) = self.histories.get_mut(&token.id) {
+            previous.push_front(Box::new(old));
+            previous.truncate(depth);
+        }
+    }
+
+    /// The previous values of a value, most recent first
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn history<V: Any + 'static>(&self, token: &Token<V>) -> Vec<&V> {
+        if !self.contains(token) {
+            self.invalid_access(token);
+        }
+        match self.histories.get(&token.id) {
+            Some((_, previous)) => previous.iter().map(|v| v.downcast_ref().unwrap()).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Restore a previous value, `steps` replacements back
+    ///
+    /// The current value and the ones more recent than the restored one are
+    /// dropped. Returns `false` and leaves the value untouched if its history
+    /// is not that deep.
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn rollback<V: Any + 'static>(&mut self, token: &Token<V>, steps: usize) -> bool {
+        if !self.contains(token) {
+            self.invalid_access(token);
+        }
+        if steps == 0 {
+            return true;
+        }
+        let restored = match self.histories.get_mut(&token.id) {
+            Some(&mut (_, ref mut previous)) if previous.len() >= steps => {
+                let restored = previous.remove(steps - 1).unwrap();
+                previous.drain(..steps - 1);
+                restored
+            }
+            _ => return false,
+        };
+        self.replace(token, *restored.downcast::<V>().unwrap());
+        true
+    }
+
+    // Drops the history of a slot
+    pub(crate) fn clear_history(&mut self, id: usize) {
+        if !self.histories.is_empty() {
+            self.histories.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history() {
+        let mut store = Store::new();
+        let token = store.insert(0);
+        store.enable_history(&token, 2);
+        for i in 1..4 {
+            store.replace_recorded(&token, i);
+        }
+        assert_eq!(store.history(&token), vec![&2, &1]);
+        assert!(!store.rollback(&token, 3));
+        assert!(store.rollback(&token, 2));
+        assert_eq!(*store.get(&token), 1);
+        assert!(store.history(&token).is_empty());
+        store.remove(token);
+        assert!(store.histories.is_empty());
+    }
+}
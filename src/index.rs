@@ -0,0 +1,172 @@
+use super::{Store, Token};
+use std::any::{Any, TypeId};
+use std::collections::{BTreeSet, HashMap};
+use std::hash::Hash;
+
+// An index over the values of a type, with its key type erased
+pub(crate) trait Indexer {
+    fn insert(&mut self, id: usize, value: &dyn Any);
+    fn remove(&mut self, id: usize);
+    fn as_any(&self) -> &dyn Any;
+}
+
+// Computes the key of a value
+type KeyFn<K> = Box<dyn Fn(&dyn Any) -> K>;
+
+struct Index<K> {
+    extract: KeyFn<K>,
+    keys: HashMap<usize, K>,
+    slots: HashMap<K, BTreeSet<usize>>,
+}
+
+impl<K: Hash + Eq + Clone + 'static> Indexer for Index<K> {
+    fn insert(&mut self, id: usize, value: &dyn Any) {
+        self.remove(id);
+        let key = (self.extract)(value);
+        self.slots.entry(key.clone()).or_default().insert(id);
+        self.keys.insert(id, key);
+    }
+
+    fn remove(&mut self, id: usize) {
+        if let Some(key) = self.keys.remove(&id) {
+            let slots = self.slots.get_mut(&key).unwrap();
+            slots.remove(&id);
+            if slots.is_empty() {
+                self.slots.remove(&key);
+            }
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// The index of each type registered with `index_by`
+pub(crate) type Indexes = HashMap<TypeId, Box<dyn Indexer>>;
+
+impl Store {
+    /// Index the values of type `V` by a key computed from them
+    ///
+    /// The index is kept up to date as values are inserted, replaced, removed
+    /// or mutably accessed, and is queried with `tokens_where`. Registering a
+    /// new index for `V` replaces the previous one.
+    pub fn index_by<V, K, F>(&mut self, extract: F)
+    where
+        V: Any + 'static,
+        K: Hash + Eq + Clone + 'static,
+        F: Fn(&V) -> K + 'static,
+    {
+        let mut index = Index {
+            extract: Box::new(move |value: &dyn Any| extract(value.downcast_ref().unwrap())),
+            keys: HashMap::new(),
+            slots: HashMap::new(),
+        };
+        for id in 0..self.values.len() {
+            let slot = &self.values[id];
+            if let (true, Some(value)) = (slot.type_id == TypeId::of::<V>(), slot.value.as_ref()) {
+                index.insert(id, &**value);
+            }
+        }
+        self.indexes.insert(TypeId::of::<V>(), Box::new(index));
+    }
+
+    /// The values of type `V` whose key is `key`, in slot order
+    ///
+    /// Panics if no index was registered for `V` with keys of type `K`.
+    pub fn tokens_where<V, K>(&mut self, key: &K) -> Vec<Token<V>>
+    where
+        V: Any + 'static,
+        K: Hash + Eq + Clone + 'static,
+    {
+        self.refresh_indexes();
+        let index = self
+            .indexes
+            .get(&TypeId::of::<V>())
+            .and_then(|index| index.as_any().downcast_ref::<Index<K>>())
+            .expect("Attempted to query an index that was not registered.");
+        match index.slots.get(key) {
+            Some(slots) => slots
+                .iter()
+                .filter_map(|&id| self.token_from_raw_parts(id as u32, self.values[id].generation))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // Records that the value of a slot may have changed
+    pub(crate) fn mark_stale(&mut self, id: usize) {
+        if let Some(slot) = self.values.get(id) {
+            if self.indexes.contains_key(&slot.type_id) {
+                self.stale.insert(id);
+            }
+        }
+    }
+
+    // Updates the value of a slot in the index of its type
+    pub(crate) fn index_insert(&mut self, id: usize) {
+        let slot = &self.values[id];
+        if let (Some(index), Some(value)) = (self.indexes.get_mut(&slot.type_id), slot.value.as_ref()) {
+            index.insert(id, &**value);
+        }
+    }
+
+    // Removes a slot from the index of its type
+    pub(crate) fn index_remove(&mut self, id: usize) {
+        self.stale.remove(&id);
+        if let Some(index) = self.indexes.get_mut(&self.values[id].type_id) {
+            index.remove(id);
+        }
+    }
+
+    // Recomputes the keys of the values that were mutably accessed
+    fn refresh_indexes(&mut self) {
+        for id in ::std::mem::take(&mut self.stale) {
+            self.index_insert(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Surface {
+        output_id: u32,
+    }
+
+    #[test]
+    fn index_by() {
+        let mut store = Store::new();
+        let first = store.insert(Surface { output_id: 1 });
+        store.index_by::<Surface, _, _>(|s| s.output_id);
+        let second = store.insert(Surface { output_id: 1 });
+        let third = store.insert(Surface { output_id: 2 });
+        assert_eq!(store.tokens_where::<Surface, _>(&1u32), vec![first.clone(), second.clone()]);
+        store.get_mut(&first).output_id = 2;
+        store.remove(second);
+        assert_eq!(store.tokens_where::<Surface, _>(&1u32), vec![]);
+        assert_eq!(store.tokens_where::<Surface, _>(&2u32), vec![first, third]);
+    }
+
+    #[test]
+    fn index_other_mutations() {
+        let mut store = Store::new();
+        store.index_by::<u32, _, _>(|&v| v);
+        let token = store.insert(1u32);
+        {
+            let mut cursor = store.cursor();
+            *cursor.next().unwrap().1.downcast_mut::<u32>().unwrap() = 2;
+        }
+        assert_eq!(store.tokens_where::<u32, _>(&2u32), vec![token.clone()]);
+        {
+            let mut cursor = store.cursor();
+            cursor.next();
+            cursor.replace_current(3u32);
+        }
+        assert_eq!(store.tokens_where::<u32, _>(&3u32), vec![token.clone()]);
+        unsafe { *store.get_unchecked_mut(&token) = 4 };
+        assert_eq!(store.tokens_where::<u32, _>(&4u32), vec![token]);
+        assert!(store.tokens_where::<u32, _>(&3u32).is_empty());
+    }
+}
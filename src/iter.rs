@@ -0,0 +1,152 @@
+//! Iterators over the contents of a store
+
+use super::{AnyToken, Store};
+use std::any::Any;
+
+/// Iterator over the values of a store, in slot order
+///
+/// See `Store::iter`.
+pub struct Iter<'a> {
+    store: &'a Store,
+    next: usize,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (AnyToken, &'a dyn Any);
+    fn next(&mut self) -> Option<(AnyToken, &'a dyn Any)> {
+        while self.next < self.store.values.len() {
+            let id = self.next;
+            self.next += 1;
+            if let Some(token) = self.store.slot_token(id) {
+                let value = self.store.values[id].value.as_ref().unwrap();
+                return Some((token, &**value));
+            }
+        }
+        None
+    }
+}
+
+/// Iterator over the values of a store, in insertion order
+///
+/// See `Store::iter_ordered`.
+pub struct OrderedIter<'a> {
+    store: &'a Store,
+    ids: ::std::vec::IntoIter<usize>,
+}
+
+impl<'a> Iterator for OrderedIter<'a> {
+    type Item = (AnyToken, &'a dyn Any);
+    fn next(&mut self) -> Option<(AnyToken, &'a dyn Any)> {
+        let id = self.ids.next()?;
+        let token = self.store.slot_token(id).unwrap();
+        let value = self.store.values[id].value.as_ref().unwrap();
+        Some((token, &**value))
+    }
+}
+
+/// Consuming iterator over the values of a store
+///
+/// The values are yielded in slot order, along with their tokens. These
+/// tokens are no longer valid, as their values were moved out of the store,
+/// but they still identify the values they referred to.
+pub struct IntoIter {
+    store: Store,
+    next: usize,
+}
+
+impl Iterator for IntoIter {
+    type Item = (AnyToken, Box<dyn Any>);
+    fn next(&mut self) -> Option<(AnyToken, Box<dyn Any>)> {
+        while self.next < self.store.values.len() {
+            let id = self.next;
+            self.next += 1;
+            if let Some(token) = self.store.slot_token(id) {
+                return Some((token, self.store.remove_at(id)));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.store.len(), Some(self.store.len()))
+    }
+}
+
+impl IntoIterator for Store {
+    type Item = (AnyToken, Box<dyn Any>);
+    type IntoIter = IntoIter;
+    fn into_iter(self) -> IntoIter {
+        IntoIter {
+            store: self,
+            next: 0,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Store {
+    type Item = (AnyToken, &'a dyn Any);
+    type IntoIter = Iter<'a>;
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl Store {
+    /// Iterate over the values of this store
+    ///
+    /// The values are yielded in the order of their slots, which is unrelated
+    /// to the order of their insertion, as the slots of removed values are
+    /// reused. See `iter_ordered` for an iteration in insertion order.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            store: self,
+            next: 0,
+        }
+    }
+
+    /// Iterate over the values of this store, in the order they were inserted
+    ///
+    /// Unlike `iter`, this order is deterministic regardless of slot reuse.
+    /// It needs to sort the slots beforehand, and is thus more costly.
+    pub fn iter_ordered(&self) -> OrderedIter<'_> {
+        let mut slots = (0..self.values.len())
+            .map(|id| (&self.values[id], id))
+            .filter(|&(slot, _)| slot.live.is_some() && slot.value.is_some())
+            .map(|(slot, id)| (slot.serial, id))
+            .collect::<Vec<_>>();
+        slots.sort_unstable();
+        OrderedIter {
+            store: self,
+            ids: slots
+                .into_iter()
+                .map(|(_, id)| id)
+                .collect::<Vec<_>>()
+                .into_iter(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn into_iter() {
+        let mut store = Store::new();
+        let token = store.insert(1);
+        store.insert("I like trains");
+        let mut count = 0;
+        for (_, value) in &store {
+            assert!(value.is::<i32>() || value.is::<&str>());
+            count += 1;
+        }
+        assert_eq!(count, 2);
+        let numbers = store
+            .into_iter()
+            .filter_map(|(_, value)| value.downcast::<i32>().ok())
+            .map(|value| *value)
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, vec![1]);
+        assert!(!token.is_valid());
+    }
+}
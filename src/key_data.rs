@@ -0,0 +1,101 @@
+use super::{AnyToken, Store, Token};
+use std::any::Any;
+
+/// A token as a plain 64-bit key
+///
+/// This is the slot index and the generation of a value, laid out like the
+/// `KeyData` of `slotmap` and similar generational arenas: `as_ffi` puts the
+/// generation in the high 32 bits and the index in the low 32 bits. This
+/// allows data structures keyed by such integers to be reused with tokens.
+///
+/// A `KeyData` is obtained from a token with `From`, and resolved back into
+/// a token with `Store::token_from_key_data`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct KeyData {
+    index: u32,
+    generation: u32,
+}
+
+impl KeyData {
+    /// Build a key from its index and generation
+    pub fn new(index: u32, generation: u32) -> KeyData {
+        KeyData { index, generation }
+    }
+
+    /// The slot index of this key
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// The generation of this key
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Encode this key as an integer
+    pub fn as_ffi(self) -> u64 {
+        (u64::from(self.generation) << 32) | u64::from(self.index)
+    }
+
+    /// Decode a key encoded by `as_ffi`
+    pub fn from_ffi(value: u64) -> KeyData {
+        KeyData {
+            index: value as u32,
+            generation: (value >> 32) as u32,
+        }
+    }
+}
+
+impl<'a, V> ::std::convert::From<&'a Token<V>> for KeyData {
+    fn from(token: &'a Token<V>) -> KeyData {
+        KeyData::new(token.id as u32, token.generation)
+    }
+}
+
+impl<'a> ::std::convert::From<&'a AnyToken> for KeyData {
+    fn from(token: &'a AnyToken) -> KeyData {
+        KeyData::new(token.id as u32, token.generation)
+    }
+}
+
+impl ::std::convert::From<u64> for KeyData {
+    fn from(value: u64) -> KeyData {
+        KeyData::from_ffi(value)
+    }
+}
+
+impl ::std::convert::From<KeyData> for u64 {
+    fn from(key: KeyData) -> u64 {
+        key.as_ffi()
+    }
+}
+
+impl Store {
+    /// Resolve a key obtained from a token
+    ///
+    /// Returns `None` if the key does not designate a live value of type `V`.
+    pub fn token_from_key_data<V: Any + 'static>(&self, key: KeyData) -> Option<Token<V>> {
+        self.token_from_raw_parts(key.index, key.generation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_data_roundtrip() {
+        let mut store = Store::new();
+        store.insert(0u8);
+        let token = store.insert(42);
+        let key = KeyData::from(&token);
+        assert_eq!(key.as_ffi(), token.to_handle());
+        assert_eq!(KeyData::from(key.as_ffi()), key);
+        assert_eq!(KeyData::from(&AnyToken::from(&token)), key);
+        let back = store.token_from_key_data::<i32>(key).unwrap();
+        assert_eq!(back, token);
+        assert!(store.token_from_key_data::<u8>(key).is_none());
+        store.remove(token);
+        assert!(store.token_from_key_data::<i32>(key).is_none());
+    }
+}
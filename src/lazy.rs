@@ -0,0 +1,98 @@
+use super::{Store, Token};
+use std::any::Any;
+use std::cell::{Cell, OnceCell};
+
+/// A value constructed on first access
+///
+/// It is stored by `Store::insert_lazy`, and its initialization function
+/// only runs the first time the value is accessed.
+pub struct Lazy<V> {
+    value: OnceCell<V>,
+    init: Cell<Option<Box<dyn FnOnce() -> V>>>,
+}
+
+impl<V> Lazy<V> {
+    /// Create a lazy value from its initialization function
+    pub fn new<F: FnOnce() -> V + 'static>(init: F) -> Lazy<V> {
+        Lazy {
+            value: OnceCell::new(),
+            init: Cell::new(Some(Box::new(init))),
+        }
+    }
+
+    /// Access the value, initializing it if needed
+    ///
+    /// Panics if a previous initialization attempt panicked.
+    pub fn get(&self) -> &V {
+        self.value.get_or_init(|| match self.init.take() {
+            Some(init) => init(),
+            None => panic!("Attempted to access a lazy value whose initialization panicked!"),
+        })
+    }
+
+    /// Mutably access the value, initializing it if needed
+    ///
+    /// Panics if a previous initialization attempt panicked.
+    pub fn get_mut(&mut self) -> &mut V {
+        self.get();
+        self.value.get_mut().unwrap()
+    }
+
+    /// Check wether the value was already initialized
+    pub fn is_initialized(&self) -> bool {
+        self.value.get().is_some()
+    }
+}
+
+impl Store {
+    /// Insert a value that will be constructed on first access
+    ///
+    /// `init` is only called the first time the value is accessed through
+    /// `get_lazy` or `get_lazy_mut`.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn insert_lazy<V: Any + 'static, F: FnOnce() -> V + 'static>(&mut self, init: F) -> Token<Lazy<V>> {
+        self.insert(Lazy::new(init))
+    }
+
+    /// Access a value inserted with `insert_lazy`, initializing it if needed
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn get_lazy<V: Any + 'static>(&self, token: &Token<Lazy<V>>) -> &V {
+        self.get(token).get()
+    }
+
+    /// Mutably access a value inserted with `insert_lazy`, initializing it if
+    /// needed
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn get_lazy_mut<V: Any + 'static>(&mut self, token: &Token<Lazy<V>>) -> &mut V {
+        self.get_mut(token).get_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn lazy_init() {
+        let calls = Rc::new(Cell::new(0));
+        let mut store = Store::new();
+        let token = {
+            let calls = calls.clone();
+            store.insert_lazy(move || {
+                calls.set(calls.get() + 1);
+                String::from("I like trains")
+            })
+        };
+        assert_eq!(calls.get(), 0);
+        assert!(!store.get(&token).is_initialized());
+        assert_eq!(store.get_lazy(&token), "I like trains");
+        store.get_lazy_mut(&token).push('!');
+        assert_eq!(store.get_lazy(&token), "I like trains!");
+        assert_eq!(calls.get(), 1);
+    }
+}
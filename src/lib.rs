@@ -33,7 +33,9 @@
 //! parts of your code.
 //!
 //! Note however that, as it is possible to store `!Send` types in the `token_store`,
-//! neither the store nor its tokens can be shared accross threads.
+//! the store can not be shared accross threads. Its tokens can be sent to other
+//! threads if their value type is `Send`, for example to be sent back later, but
+//! they can only be used with the store on its own thread.
 //!
 //! ## Value scopes and genericity
 //!
@@ -79,120 +81,1526 @@
 //! ```
 #![warn(missing_docs)]
 
-use std::any::Any;
+use chunked::ChunkedVec;
+use clone::CloneFn;
+use coalesce::Coalesced;
+use diff::EqFn;
+use effects::Effect;
+use finalize::Finalizer;
+use history::Histories;
+use index::Indexes;
+use limits::{Quota, QuotaHandler};
+use links::Links;
+use mailbox::Mailboxes;
+use policy::AccessHook;
+use recycle::Pools;
+use salt::next_salt;
+use schedule::Schedule;
+use stable::Stored;
+use tasks::PollFn;
+use timestamps::{Times, Timestamps};
+use tombstone::Tombstones;
+use validator::ValidatorFn;
+use watch::TypeWatchers;
+use reverse::value_address;
+use weak::DeadFn;
+use std::any::{type_name, Any, TypeId};
 use std::borrow::Cow;
-use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
 use std::marker::PhantomData;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::cmp::Ordering as CmpOrdering;
+use std::cmp::Reverse as CmpReverse;
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+#[macro_use]
+mod macros;
+
+pub use alias::AliasToken;
+pub use blob::Blob;
+pub use bound::BoundToken;
+pub use builder::StoreBuilder;
+pub use callback::Callback;
+pub use coalesce::Coalescing;
+pub use brand::{BrandedStore, BrandedToken};
+pub use deferred::CommandBuffer;
+pub use derived::Derived;
+pub use diff::Change;
+pub use extensions::Extensions;
+pub use handle::StoreHandle;
+pub use key_data::KeyData;
+pub use lazy::Lazy;
+pub use manifest::{Manifest, TypeEntry};
+pub use overflow::GenerationOverflow;
+pub use poison::AccessError;
+pub use policy::AccessFailure;
+pub use projection::ProjToken;
+pub use pubsub::Subscriber;
+pub use registry::ThreadStoreRegistry;
+pub use scoped::Scoped;
+pub use secondary::{Key, SparseSecondaryMap};
+pub use service::{RemoteToken, StoreService};
+pub use stable::StableRef;
+pub use tasks::Task;
+pub use validate::ValidationReport;
+pub use version::Version;
+
+mod alias;
+mod blob;
+mod bound;
+mod brand;
+mod builder;
+mod callback;
+mod chunked;
+mod clone;
+mod coalesce;
+mod confine;
+pub mod cursor;
+mod deferred;
+mod derived;
+mod diff;
+mod effects;
+mod eviction;
+mod export;
+mod extensions;
+mod finalize;
+mod flags;
+pub mod global;
+mod handle;
+mod history;
+mod index;
+mod key_data;
+mod lazy;
+mod limits;
+mod links;
+mod mailbox;
+mod maintain;
+mod manifest;
+mod overflow;
+mod partition;
+pub mod iter;
+mod poison;
+mod policy;
+mod projection;
+mod pubsub;
+mod recycle;
+mod registry;
+mod salt;
+mod reverse;
+mod schedule;
+mod scoped;
+mod seal;
+mod silence;
+pub mod secondary;
+mod service;
+mod stable;
+mod stable_id;
+mod tasks;
+mod timestamps;
+mod tombstone;
+mod validate;
+mod validator;
+mod version;
+mod watch;
+mod weak;
+#[cfg(feature = "capi")]
+pub mod capi;
 
 /// A token store
 ///
 /// This struct allows you to store various values in a store
 /// and access them back using the provided tokens.
+///
+/// When the store is dropped, the finalizers of the values it still holds are
+/// run, and then the remaining values are dropped, both in reverse insertion
+/// order. The finalizers of the values inserted by other finalizers at that
+/// point are not run.
 pub struct Store {
-    values: Vec<Option<(Box<Any>, Rc<Cell<bool>>)>>,
+    values: ChunkedVec<Slot>,
+    // indices of the vacant slots, reused in LIFO order
+    free: VecDeque<usize>,
+    listeners: Vec<Sender<StoreEvent>>,
+    coalesced: Coalesced,
+    type_watchers: TypeWatchers,
+    // number of events suppressed by `silenced` for each watched type
+    silenced_types: HashMap<TypeId, usize>,
+    // mixed into the first generation of each slot, see `set_generation_salting`
+    salt: Option<u64>,
+    // liveness cells of removed values that are no longer referenced by any
+    // token, kept to be reused by later insertions
+    cell_pool: Vec<LiveCell>,
+    // number of values in the store
+    len: usize,
+    eviction_limit: Option<usize>,
+    max_len: Option<usize>,
+    quotas: HashMap<TypeId, Quota>,
+    quota_handler: Option<QuotaHandler>,
+    access_hook: Option<AccessHook>,
+    // number of events suppressed by `silenced`, if it is running
+    silenced: Option<usize>,
+    // wether insertions are forbidden, see `seal`
+    sealed: bool,
+    // wether vacant slots are reused by insertions, and in which order
+    reuse_slots: bool,
+    reuse_order: ReuseOrder,
+    generation_overflow: GenerationOverflow,
+    // slots whose generation counter is exhausted, never reused again
+    retired: HashSet<usize>,
+    // how to clone the values of each type registered with `register_clone`
+    cloners: HashMap<TypeId, CloneFn>,
+    // how to compare the values of each type registered with `register_eq`
+    comparators: HashMap<TypeId, EqFn>,
+    // tokens of the values scheduled for removal by `mark_for_removal`
+    pending_removals: RefCell<Vec<AnyToken>>,
+    mailboxes: RefCell<Mailboxes>,
+    // slot of each value by address, if reverse lookup is enabled
+    addresses: Option<HashMap<usize, usize>>,
+    links: Links,
+    effects: Vec<Effect>,
+    histories: Histories,
+    timestamps: Timestamps,
+    // how to poll the futures of each type inserted with `insert_future`
+    pollers: HashMap<TypeId, PollFn>,
+    // values hidden by `soft_remove`
+    tombstones: Tombstones,
+    // next slot to be swept by `maintain`
+    maintenance_cursor: usize,
+    schedule: Schedule,
+    indexes: Indexes,
+    // slots whose index keys must be recomputed, see `index_by`
+    stale: HashSet<usize>,
+    // how to check the values of each type registered with `set_validator`
+    validators: HashMap<TypeId, ValidatorFn>,
+    // recycled values of each type, see `enable_recycling`
+    pools: Pools,
+    // how to check the weak references of each type inserted with `insert_weak`
+    weak_types: HashMap<TypeId, DeadFn>,
+    // slot of each value with a stable id
+    stable_ids: HashMap<u64, usize>,
+    // thread owning the store, only tracked with debug assertions
+    #[cfg(debug_assertions)]
+    owner: ::std::thread::ThreadId,
+}
+
+// Whether a value is still in its store, shared by its slot and its tokens
+//
+// It is atomic so that tokens can be sent to other threads, even though the
+// store itself can not.
+#[derive(Clone)]
+struct LiveCell(Arc<AtomicBool>);
+
+impl LiveCell {
+    fn new(live: bool) -> LiveCell {
+        LiveCell(Arc::new(AtomicBool::new(live)))
+    }
+
+    fn get(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    fn set(&self, live: bool) {
+        self.0.store(live, Ordering::Release)
+    }
+
+    fn ptr_eq(&self, other: &LiveCell) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    // wether no token shares this cell
+    fn is_unique(&self) -> bool {
+        Arc::strong_count(&self.0) == 1
+    }
+}
+
+// Serials are global, so that they are unique accross all stores
+static NEXT_SERIAL: AtomicU64 = AtomicU64::new(0);
+
+// The generation and type of the value are kept inline in the slot, so that
+// checking a token only requires comparing them, without going through the
+// liveness cell or the vtable of the boxed value.
+//
+// A slot is vacant if it has no liveness cell, and reserved if it has a
+// liveness cell but no value yet.
+struct Slot {
+    generation: u32,
+    type_id: TypeId,
+    live: Option<LiveCell>,
+    value: Option<Stored>,
+    // order in which the values were inserted in the store
+    serial: u64,
+    priority: i32,
+    // user flags, see `set_flags`
+    flags: u32,
+    stable_id: Option<u64>,
+    // name of the type of the value, for diagnostics
+    type_name: &'static str,
+    // bumped on every mutable access to the value
+    version: u64,
+    // set when a closure accessing the value panicked
+    poisoned: bool,
+    finalizer: Option<Finalizer>,
+    provenance: Provenance,
+}
+
+// Where a value was inserted, only tracked with the `provenance` feature
+#[cfg(feature = "provenance")]
+type Provenance = &'static ::std::panic::Location<'static>;
+#[cfg(not(feature = "provenance"))]
+type Provenance = NoProvenance;
+
+#[cfg(not(feature = "provenance"))]
+#[derive(Clone, Copy)]
+struct NoProvenance;
+
+#[cfg(feature = "provenance")]
+#[track_caller]
+fn caller_provenance() -> Provenance {
+    ::std::panic::Location::caller()
 }
 
-/// A token for accessing the store contents
-pub struct Token<V> {
-    id: usize,
-    live: Rc<Cell<bool>>,
-    _type: PhantomData<V>,
-}
+#[cfg(not(feature = "provenance"))]
+fn caller_provenance() -> Provenance {
+    NoProvenance
+}
+
+/// A token for accessing the store contents
+pub struct Token<V> {
+    id: usize,
+    generation: u32,
+    serial: u64,
+    live: LiveCell,
+    provenance: Provenance,
+    _type: PhantomData<V>,
+}
+
+impl<V> Token<V> {
+    /// Check wether this token is still valid
+    ///
+    /// If it is not, trying to use it to access contents
+    /// will panic.
+    ///
+    /// This only requires the token: its liveness is shared with
+    /// the store, so code holding only a token can check it before
+    /// doing any work.
+    pub fn is_valid(&self) -> bool {
+        self.live.get()
+    }
+
+    /// Where the value of this token was inserted in the store
+    ///
+    /// Only available with the `provenance` cargo feature.
+    #[cfg(feature = "provenance")]
+    pub fn inserted_at(&self) -> &'static ::std::panic::Location<'static> {
+        self.provenance
+    }
+
+    /// The creation serial of this token
+    ///
+    /// Every value inserted in a store is given a serial, which is strictly
+    /// greater than the serials of all values inserted before it, in any store.
+    /// Tokens are ordered by their serial, and thus by creation time.
+    pub fn serial(&self) -> u64 {
+        self.serial
+    }
+
+    /// Check wether this token is still valid
+    ///
+    /// This is the same as `is_valid`.
+    pub fn valid(&self) -> bool {
+        self.is_valid()
+    }
+
+    /// Decompose this token into its raw parts
+    ///
+    /// Returns the slot index and the generation of the value this
+    /// token refers to. These integers can be carried through code that
+    /// cannot hold a Rust value (C callbacks, GPU buffers...), and turned
+    /// back into a token using `Store::token_from_raw_parts`.
+    pub fn into_raw_parts(self) -> (u32, u32) {
+        (self.id as u32, self.generation)
+    }
+
+    /// Encode this token as a single integer handle
+    ///
+    /// The handle contains both the slot index and the generation of the
+    /// value, and can be resolved back into a token using
+    /// `Store::token_from_handle`.
+    pub fn to_handle(&self) -> u64 {
+        (u64::from(self.generation) << 32) | self.id as u64
+    }
+
+    /// Encode this token as a handle fitting losslessly in a `f64`
+    ///
+    /// This is meant for handles crossing a JavaScript boundary, where
+    /// numbers are doubles: the handle is an integer smaller than 2^53.
+    /// Only the lower 21 bits of the generation fit in it alongside the
+    /// slot index, which is still plenty to catch stale handles.
+    ///
+    /// It can be resolved back into a token using `Store::token_from_f64_handle`.
+    pub fn to_f64_handle(&self) -> f64 {
+        let generation = u64::from(self.generation & F64_GENERATION_MASK);
+        ((generation << 32) | self.id as u64) as f64
+    }
+}
+
+const F64_GENERATION_MASK: u32 = (1 << 21) - 1;
+
+impl<V> Clone for Token<V> {
+    fn clone(&self) -> Token<V> {
+        Token {
+            id: self.id,
+            generation: self.generation,
+            serial: self.serial,
+            live: self.live.clone(),
+            provenance: self.provenance,
+            _type: PhantomData,
+        }
+    }
+}
+
+/// A type-erased token
+///
+/// This token refers to a value of the store without keeping
+/// track of its type. It can be obtained from any `Token<V>`, and
+/// is useful for code that only needs to reason about the
+/// presence of values, not their contents.
+pub struct AnyToken {
+    id: usize,
+    generation: u32,
+    serial: u64,
+    live: LiveCell,
+    provenance: Provenance,
+}
+
+impl AnyToken {
+    /// Check wether this token is still valid
+    pub fn is_valid(&self) -> bool {
+        self.live.get()
+    }
+
+    /// The creation serial of this token
+    ///
+    /// See `Token::serial`.
+    pub fn serial(&self) -> u64 {
+        self.serial
+    }
+
+    /// Where the value of this token was inserted in the store
+    ///
+    /// Only available with the `provenance` cargo feature.
+    #[cfg(feature = "provenance")]
+    pub fn inserted_at(&self) -> &'static ::std::panic::Location<'static> {
+        self.provenance
+    }
+}
+
+impl Clone for AnyToken {
+    fn clone(&self) -> AnyToken {
+        AnyToken {
+            id: self.id,
+            generation: self.generation,
+            serial: self.serial,
+            live: self.live.clone(),
+            provenance: self.provenance,
+        }
+    }
+}
+
+impl<V> fmt::Debug for Token<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Token")
+            .field("id", &self.id)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+impl fmt::Debug for AnyToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AnyToken")
+            .field("id", &self.id)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+// Tokens are identified by their serial, which is unique
+impl<V> PartialEq for Token<V> {
+    fn eq(&self, other: &Token<V>) -> bool {
+        self.serial == other.serial
+    }
+}
+
+impl<V> Eq for Token<V> {}
+
+impl<V> PartialOrd for Token<V> {
+    fn partial_cmp(&self, other: &Token<V>) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<V> Ord for Token<V> {
+    fn cmp(&self, other: &Token<V>) -> CmpOrdering {
+        self.serial.cmp(&other.serial)
+    }
+}
+
+impl<V> Hash for Token<V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.serial.hash(state)
+    }
+}
+
+impl PartialEq for AnyToken {
+    fn eq(&self, other: &AnyToken) -> bool {
+        self.serial == other.serial
+    }
+}
+
+impl Eq for AnyToken {}
+
+impl PartialOrd for AnyToken {
+    fn partial_cmp(&self, other: &AnyToken) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AnyToken {
+    fn cmp(&self, other: &AnyToken) -> CmpOrdering {
+        self.serial.cmp(&other.serial)
+    }
+}
+
+impl Hash for AnyToken {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.serial.hash(state)
+    }
+}
+
+impl<V> ::std::convert::From<Token<V>> for AnyToken {
+    fn from(token: Token<V>) -> AnyToken {
+        AnyToken {
+            id: token.id,
+            generation: token.generation,
+            serial: token.serial,
+            live: token.live,
+            provenance: token.provenance,
+        }
+    }
+}
+
+impl<'a, V> ::std::convert::From<&'a Token<V>> for AnyToken {
+    fn from(token: &'a Token<V>) -> AnyToken {
+        AnyToken {
+            id: token.id,
+            generation: token.generation,
+            serial: token.serial,
+            live: token.live.clone(),
+            provenance: token.provenance,
+        }
+    }
+}
+
+/// A mutation of a store
+///
+/// See `Store::events`.
+#[derive(Debug, Clone)]
+pub enum StoreEvent {
+    /// A value was inserted
+    Inserted(AnyToken),
+    /// A value was removed, its token is no longer valid
+    Removed(AnyToken),
+    /// A value was replaced by a new one, using `Store::replace`
+    Replaced(AnyToken),
+    /// This number of mutations were made in a call to `Store::silenced`
+    Bulk(usize),
+}
+
+/// The order in which removed slots are reused by insertions
+///
+/// See `Store::set_reuse_order`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReuseOrder {
+    /// The most recently freed slot is reused first, which is the default
+    ///
+    /// It is the most cache-friendly order.
+    Lifo,
+    /// The least recently freed slot is reused first
+    ///
+    /// It maximizes the time before a slot is reused, and thus the time
+    /// during which stale tokens are detected as such.
+    Fifo,
+}
+
+impl Default for Store {
+    fn default() -> Store {
+        Store::new()
+    }
+}
+
+impl Drop for Store {
+    fn drop(&mut self) {
+        let hidden = self.tombstones.keys().cloned().collect::<Vec<_>>();
+        for id in hidden {
+            self.restore_tombstone(id);
+        }
+        self.run_finalizers();
+        // drop the values in reverse insertion order, so that values are
+        // dropped before the ones that existed when they were inserted
+        let mut ids = (0..self.values.len())
+            .filter(|&id| self.values[id].value.is_some())
+            .collect::<Vec<_>>();
+        ids.sort_by_key(|&id| CmpReverse(self.values[id].serial));
+        for id in ids {
+            self.values[id].value = None;
+        }
+        // tokens that outlive the store are no longer valid
+        for id in 0..self.values.len() {
+            if let Some(ref live) = self.values[id].live {
+                live.set(false);
+            }
+        }
+    }
+}
+
+impl<V: Any + 'static> Extend<V> for Store {
+    #[cfg_attr(feature = "provenance", track_caller)]
+    fn extend<I: IntoIterator<Item = V>>(&mut self, values: I) {
+        for value in values {
+            self.insert_sparing(value, &[]);
+        }
+    }
+}
+
+impl Store {
+    /// Create a new store
+    pub fn new() -> Store {
+        Store {
+            values: ChunkedVec::new(),
+            free: VecDeque::new(),
+            listeners: Vec::new(),
+            coalesced: Coalesced::default(),
+            type_watchers: Vec::new(),
+            silenced_types: HashMap::new(),
+            salt: next_salt(),
+            cell_pool: Vec::new(),
+            len: 0,
+            eviction_limit: None,
+            max_len: None,
+            quotas: HashMap::new(),
+            quota_handler: None,
+            access_hook: None,
+            silenced: None,
+            sealed: false,
+            reuse_slots: true,
+            reuse_order: ReuseOrder::Lifo,
+            generation_overflow: GenerationOverflow::Wrap,
+            retired: HashSet::new(),
+            cloners: HashMap::new(),
+            comparators: HashMap::new(),
+            pending_removals: RefCell::new(Vec::new()),
+            mailboxes: RefCell::new(HashMap::new()),
+            addresses: None,
+            links: HashMap::new(),
+            effects: Vec::new(),
+            histories: HashMap::new(),
+            timestamps: None,
+            stable_ids: HashMap::new(),
+            weak_types: HashMap::new(),
+            pools: HashMap::new(),
+            tombstones: HashMap::new(),
+            maintenance_cursor: 0,
+            schedule: BTreeMap::new(),
+            indexes: HashMap::new(),
+            stale: HashSet::new(),
+            validators: HashMap::new(),
+            pollers: HashMap::new(),
+            #[cfg(debug_assertions)]
+            owner: ::std::thread::current().id(),
+        }
+    }
+
+    /// Subscribe to the mutations of this store
+    ///
+    /// Every insertion, removal or replacement of a value is reported as a
+    /// `StoreEvent` on the returned receiver, in the order they happen.
+    /// External code like indexes or UIs can then follow the contents of
+    /// the store without being involved in every mutation.
+    ///
+    /// The receiver can be dropped at any time to unsubscribe.
+    pub fn events(&mut self) -> Receiver<StoreEvent> {
+        let (sender, receiver) = channel();
+        self.listeners.push(sender);
+        receiver
+    }
+
+    fn emit(&mut self, event: StoreEvent) {
+        if let Some(ref mut count) = self.silenced {
+            *count += 1;
+            if !self.type_watchers.is_empty() {
+                self.count_silenced(&event);
+            }
+            return;
+        }
+        self.coalesce(&event);
+        if !self.type_watchers.is_empty() {
+            self.notify_type_watchers(&event);
+        }
+        if !self.listeners.is_empty() {
+            self.listeners
+                .retain(|listener| listener.send(event.clone()).is_ok());
+        }
+    }
+
+    /// Check wether the value represented by this token is stored in this store
+    ///
+    /// Unlike the access methods, this never panics: it returns `false` if the
+    /// value was removed, or if the token was created by another store.
+    pub fn is_alive(&self, token: &AnyToken) -> bool {
+        match self.values.get(token.id) {
+            Some(&Slot {
+                live: Some(ref live),
+                value: Some(_),
+                ..
+            }) => live.ptr_eq(&token.live) && live.get(),
+            _ => false,
+        }
+    }
+
+    /// Check wether this store contains the value represented by this token
+    ///
+    /// This is the typed counterpart of `is_alive`, it returns `true` if and
+    /// only if accessing the value with this token would succeed.
+    pub fn contains<V: Any + 'static>(&self, token: &Token<V>) -> bool {
+        match self.values.get(token.id) {
+            Some(&Slot {
+                live: Some(ref live),
+                ..
+            }) => live.ptr_eq(&token.live) && self.lookup::<V>(token.id, token.generation).is_some(),
+            _ => false,
+        }
+    }
+
+    /// The type of the value represented by this token
+    ///
+    /// Returns `None` if the value was removed from this store.
+    pub fn type_id_of(&self, token: &AnyToken) -> Option<TypeId> {
+        if self.is_alive(token) {
+            Some(self.values[token.id].type_id)
+        } else {
+            None
+        }
+    }
+
+    /// The name of the type of the value represented by this token
+    ///
+    /// The name is the one given by `std::any::type_name`, and is only meant
+    /// for diagnostics. Returns `None` if the value was removed from this
+    /// store.
+    pub fn type_name_of(&self, token: &AnyToken) -> Option<&'static str> {
+        if self.is_alive(token) {
+            Some(self.values[token.id].type_name)
+        } else {
+            None
+        }
+    }
+
+    /// Rebuild a token from its raw parts
+    ///
+    /// The parts are typically obtained from `Token::into_raw_parts`.
+    ///
+    /// The slot index, the generation and the type of the stored value are all
+    /// checked, so the returned token is always valid for this store. Returns
+    /// `None` if the parts do not designate a live value of type `V`.
+    pub fn token_from_raw_parts<V: Any + 'static>(&self, index: u32, generation: u32) -> Option<Token<V>> {
+        let id = index as usize;
+        self.lookup::<V>(id, generation)?;
+        let slot = &self.values[id];
+        Some(Token {
+            id,
+            generation,
+            serial: slot.serial,
+            live: slot.live.clone()?,
+            provenance: slot.provenance,
+            _type: PhantomData,
+        })
+    }
+
+    /// Resolve a handle created by `Token::to_handle`
+    ///
+    /// Returns `None` if the handle does not designate a live value of type `V`,
+    /// for example if the value it referred to was removed.
+    pub fn token_from_handle<V: Any + 'static>(&self, handle: u64) -> Option<Token<V>> {
+        self.token_from_raw_parts(handle as u32, (handle >> 32) as u32)
+    }
+
+    /// Resolve a handle created by `Token::to_f64_handle`
+    ///
+    /// Returns `None` if the handle is not a valid handle, or if it does not
+    /// designate a live value of type `V`.
+    pub fn token_from_f64_handle<V: Any + 'static>(&self, handle: f64) -> Option<Token<V>> {
+        if !(handle >= 0.0 && handle < (1u64 << 53) as f64 && handle.fract() == 0.0) {
+            return None;
+        }
+        let handle = handle as u64;
+        let id = handle as u32 as usize;
+        let generation = (handle >> 32) as u32;
+        let slot = self.values.get(id)?;
+        if slot.generation & F64_GENERATION_MASK != generation {
+            return None;
+        }
+        self.token_from_raw_parts(id as u32, slot.generation)
+    }
+
+    /// Insert a new value in this store
+    ///
+    /// Returns a clonable token that you can later use to access this
+    /// value.
+    ///
+    /// Every value is boxed on its own, so its alignment is always honoured,
+    /// including for over-aligned types. Zero-sized values, like marker
+    /// types, are not allocated: only their slot and the liveness cell of
+    /// their tokens are, and both are reused once the value is removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn insert<V: Any + 'static>(&mut self, value: V) -> Token<V> {
+        self.insert_sparing(value, &[])
+    }
+
+    /// Insert all the values of an iterator in this store
+    ///
+    /// Returns the tokens of the new values, in the order the iterator
+    /// yielded them.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn insert_iter<V: Any + 'static, I: IntoIterator<Item = V>>(&mut self, values: I) -> Vec<Token<V>> {
+        let values = values.into_iter();
+        let mut tokens = Vec::with_capacity(values.size_hint().0);
+        for value in values {
+            tokens.push(self.insert_sparing(value, &[]));
+        }
+        tokens
+    }
+
+    // Inserts a value, never evicting the values of the `spared` slots
+    #[cfg_attr(feature = "provenance", track_caller)]
+    fn insert_sparing<V: Any + 'static>(&mut self, value: V, spared: &[usize]) -> Token<V> {
+        self.assert_valid(&value);
+        self.make_room(spared);
+        let token = self.allocate::<V>();
+        self.occupy(&token, value);
+        token
+    }
+
+    // Evicts values until an insertion would not exceed the eviction limit
+    fn make_room(&mut self, spared: &[usize]) {
+        if let Some(limit) = self.eviction_limit {
+            while self.len >= limit {
+                match self.lowest_sparing(1, spared).pop() {
+                    Some(id) => self.finalize_at(id),
+                    None => break,
+                }
+            }
+        }
+    }
+
+    // Stores the value of a token freshly returned by `allocate`
+    fn occupy<V: Any + 'static>(&mut self, token: &Token<V>, value: V) {
+        self.occupy_any(AnyToken::from(token), Box::new(value));
+    }
+
+    // Stores the value of a token freshly returned by `allocate_any`
+    fn occupy_any(&mut self, token: AnyToken, value: Box<dyn Any>) {
+        if let Some(ref mut addresses) = self.addresses {
+            if let Some(address) = value_address(&*value) {
+                addresses.insert(address, token.id);
+            }
+        }
+        self.values[token.id].value = Some(Stored::new(value));
+        if let Some(ref mut timestamps) = self.timestamps {
+            timestamps.insert(token.id, Times::new());
+        }
+        if !self.indexes.is_empty() {
+            self.index_insert(token.id);
+        }
+        self.len += 1;
+        self.emit(StoreEvent::Inserted(token));
+    }
+
+    /// Insert a new value in a specific slot of this store
+    ///
+    /// This is meant to reproduce the slot assignment of another store, for
+    /// example when replaying a recorded session. The store grows as needed
+    /// for slot `id` to exist.
+    ///
+    /// If this slot is already in use or retired, if the store is full or
+    /// sealed, if the quota of `V` is reached, or if the value is rejected by
+    /// the validator of `V`, the value is given back as an error, and nothing
+    /// is evicted.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn insert_with_id<V: Any + 'static>(&mut self, id: usize, value: V) -> Result<Token<V>, V> {
+        let provenance = caller_provenance();
+        if self.sealed || self.check_value(&value).is_err() {
+            return Err(value);
+        }
+        if let Some(&Slot { live: Some(_), .. }) = self.values.get(id) {
+            return Err(value);
+        }
+        // a retired slot cannot be reused
+        if id < self.values.len() && !self.free.contains(&id) {
+            return Err(value);
+        }
+        if self.is_full_after(self.pending_evictions(&[]))
+            || self.quota_reached(TypeId::of::<V>(), type_name::<V>())
+        {
+            return Err(value);
+        }
+        self.make_room(&[]);
+        while self.values.len() <= id {
+            let vacant = self.push_vacant(provenance);
+            self.free.push_back(vacant);
+        }
+        let position = self.free.iter().rposition(|&free| free == id).unwrap();
+        self.free.remove(position);
+        let token = self.allocate_at::<V>(id, provenance);
+        self.occupy(&token, value);
+        Ok(token)
+    }
+
+    /// Set wether removed slots are reused by later insertions
+    ///
+    /// Slots are reused by default. When this is disabled, every insertion
+    /// takes a new slot at the end of the store, so that slot ids only
+    /// depend on the number of insertions. This makes them reproducible
+    /// across runs, at the cost of the store never shrinking.
+    ///
+    /// It also guarantees that a stale token can never resolve to a newer
+    /// value inserted in the same slot, which helps catching use-after-remove
+    /// bugs in debug builds.
+    pub fn set_slot_reuse(&mut self, reuse: bool) {
+        self.reuse_slots = reuse;
+    }
+
+    /// Set the order in which removed slots are reused
+    ///
+    /// Defaults to `ReuseOrder::Lifo`. This has no effect when slot reuse
+    /// is disabled by `set_slot_reuse`.
+    pub fn set_reuse_order(&mut self, order: ReuseOrder) {
+        self.reuse_order = order;
+    }
+
+    /// Number of values in this store
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check wether this store contains no values
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Reserve slots for values that will be inserted later
+    ///
+    /// Returns `n` tokens for values of type `V` that do not exist yet. These
+    /// tokens can be handed out right away, and their values provided later
+    /// using `fill`. Until then, trying to access their values will panic.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn reserve_tokens<V: Any + 'static>(&mut self, n: usize) -> Vec<Token<V>> {
+        let mut tokens = Vec::with_capacity(n);
+        for _ in 0..n {
+            tokens.push(self.allocate::<V>());
+        }
+        tokens
+    }
+
+    /// Provide the value of a token created by `reserve_tokens`
+    ///
+    /// Panics if this token was not reserved, if its value was already
+    /// provided, or if the value is rejected by the validator of `V`.
+    pub fn fill<V: Any + 'static>(&mut self, token: &Token<V>, value: V) {
+        match self.values.get(token.id) {
+            Some(&Slot {
+                generation,
+                type_id,
+                live: Some(_),
+                value: None,
+                ..
+            }) if generation == token.generation
+                && type_id == TypeId::of::<V>()
+                && !self.tombstones.contains_key(&token.id) => {}
+            _ => panic!("Attempted to fill a token that was not reserved!"),
+        }
+        self.assert_valid(&value);
+        self.occupy(token, value);
+    }
+
+    // Assigns a slot for a value of type V, marking it as reserved
+    #[cfg_attr(feature = "provenance", track_caller)]
+    fn allocate<V: Any + 'static>(&mut self) -> Token<V> {
+        self.check_thread();
+        let provenance = caller_provenance();
+        if self.sealed {
+            panic!("Attempted to insert a value in a sealed Store!");
+        }
+        if self.is_full() {
+            panic!("Attempted to insert a value in a full Store!");
+        }
+        if self.quota_reached(TypeId::of::<V>(), type_name::<V>()) {
+            panic!("Attempted to insert a value of type {} beyond its quota!", type_name::<V>());
+        }
+        let free = match (self.reuse_slots, self.reuse_order) {
+            (false, _) => None,
+            (true, ReuseOrder::Lifo) => self.free.pop_back(),
+            (true, ReuseOrder::Fifo) => self.free.pop_front(),
+        };
+        let id = match free {
+            Some(id) => id,
+            None => self.push_vacant(provenance),
+        };
+        self.allocate_at(id, provenance)
+    }
+
+    // Adds a new vacant slot at the end of the store, returning its index
+    fn push_vacant(&mut self, provenance: Provenance) -> usize {
+        if self.values.len() > u32::MAX as usize {
+            panic!("Attempted to store more than u32::MAX values in the Store!");
+        }
+        let generation = self.initial_generation(self.values.len());
+        self.values.push(Slot {
+            generation,
+            type_id: TypeId::of::<()>(),
+            live: None,
+            value: None,
+            serial: 0,
+            priority: 0,
+            flags: 0,
+            stable_id: None,
+            type_name: "()",
+            version: 0,
+            poisoned: false,
+            finalizer: None,
+            provenance,
+        });
+        self.values.len() - 1
+    }
+
+    // Marks the vacant slot `id` as reserved for a value of type V
+    fn allocate_at<V: Any + 'static>(&mut self, id: usize, provenance: Provenance) -> Token<V> {
+        let token = self.allocate_any(id, TypeId::of::<V>(), type_name::<V>(), provenance);
+        Token {
+            id,
+            generation: token.generation,
+            serial: token.serial,
+            live: token.live,
+            provenance,
+            _type: PhantomData,
+        }
+    }
+
+    // Marks the vacant slot `id` as reserved for a value of the given type
+    fn allocate_any(&mut self, id: usize, type_id: TypeId, type_name: &'static str, provenance: Provenance)
+                    -> AnyToken {
+        let live = self.fresh_cell();
+        self.count_quota(type_id, true);
+        let slot = &mut self.values[id];
+        slot.type_id = type_id;
+        slot.type_name = type_name;
+        slot.live = Some(live.clone());
+        slot.serial = NEXT_SERIAL.fetch_add(1, Ordering::Relaxed);
+        slot.priority = 0;
+        slot.flags = 0;
+        slot.version = 0;
+        slot.poisoned = false;
+        slot.provenance = provenance;
+        AnyToken {
+            id,
+            generation: slot.generation,
+            serial: slot.serial,
+            live,
+            provenance,
+        }
+    }
+
+    /// Access value previously inserted in this store
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn get<V: Any + 'static>(&self, token: &Token<V>) -> &V {
+        match self.try_get(token) {
+            Ok(value) => value,
+            Err(error) => self.failed_access(token, error),
+        }
+    }
+
+    /// Get a clone of a value previously inserted in this store
+    ///
+    /// Unlike `get`, this does not keep the store borrowed. Panics if the
+    /// provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn get_cloned<V: Any + Clone + 'static>(&self, token: &Token<V>) -> V {
+        self.get(token).clone()
+    }
+
+    /// Insert a new shared value in this store
+    ///
+    /// The value is stored behind an `Rc`, so that `get_shared` can hand out
+    /// cheap clones of it that outlive any borrow of the store.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn insert_shared<V: Any + 'static>(&mut self, value: V) -> Token<Rc<V>> {
+        self.insert_sparing(Rc::new(value), &[])
+    }
+
+    /// Get a shared handle to a value inserted with `insert_shared`
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn get_shared<V: Any + 'static>(&self, token: &Token<Rc<V>>) -> Rc<V> {
+        self.get(token).clone()
+    }
+
+    /// Mutably access value previously inserted in this store
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn get_mut<V: Any + 'static>(&mut self, token: &Token<V>) -> &mut V {
+        if let Err(error) = self.try_get(token) {
+            self.failed_access(token, error);
+        }
+        self.lookup_mut::<V>(token.id, token.generation).unwrap()
+    }
+
+    // Panics with a description of why `token` could not be used
+    #[cold]
+    #[inline(never)]
+    #[cfg_attr(feature = "provenance", track_caller)]
+    fn invalid_access<V: Any + 'static>(&self, token: &Token<V>) -> ! {
+        let state = match self.values.get(token.id) {
+            None => "this slot does not exist in this store".to_string(),
+            Some(&Slot { live: None, .. }) => "this slot is now vacant".to_string(),
+            Some(&Slot { value: None, .. }) => "this slot is reserved and was not filled yet".to_string(),
+            Some(slot) => format!(
+                "this slot now holds generation {} of type {}",
+                slot.generation, slot.type_name
+            ),
+        };
+        #[cfg(feature = "provenance")]
+        let location = format!(", value inserted at {}", token.provenance);
+        #[cfg(not(feature = "provenance"))]
+        let location = "";
+        panic!(
+            "Attempted to access a state value that was already removed! \
+             (slot {}, generation {}, type {}: {}{})",
+            token.id,
+            token.generation,
+            type_name::<V>(),
+            state,
+            location
+        );
+    }
+
+    #[inline]
+    fn lookup<V: Any + 'static>(&self, id: usize, generation: u32) -> Option<&V> {
+        self.check_thread();
+        match self.values.get(id) {
+            Some(&Slot {
+                generation: slot_generation,
+                type_id,
+                value: Some(ref boxed),
+                ..
+            }) if slot_generation == generation && type_id == TypeId::of::<V>() => {
+                // the type was just checked
+                Some(unsafe { &*(&**boxed as *const dyn Any as *const V) })
+            }
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn lookup_mut<V: Any + 'static>(&mut self, id: usize, generation: u32) -> Option<&mut V> {
+        self.check_thread();
+        if !self.indexes.is_empty() {
+            self.mark_stale(id);
+        }
+        match self.values.get_mut(id) {
+            Some(&mut Slot {
+                generation: slot_generation,
+                type_id,
+                value: Some(ref mut boxed),
+                ref mut version,
+                ..
+            }) if slot_generation == generation && type_id == TypeId::of::<V>() => {
+                *version += 1;
+                // the type was just checked
+                Some(unsafe { &mut *(&mut **boxed as *mut dyn Any as *mut V) })
+            }
+            _ => None,
+        }
+    }
+
+    /// Access value previously inserted in this store, without any check
+    ///
+    /// This is a fast path for hot loops, once the validity of a token has
+    /// already been checked, for example using `contains`. Poisoning and the
+    /// access hook are not checked, but the access is still recorded by
+    /// `enable_timestamps`.
+    ///
+    /// # Safety
+    ///
+    /// The token must have been created by this store, and its value must not
+    /// have been removed since. Otherwise the behavior is undefined.
+    pub unsafe fn get_unchecked<V: Any + 'static>(&self, token: &Token<V>) -> &V {
+        self.touch(token.id);
+        let boxed = self.values
+            .get_unchecked(token.id)
+            .value
+            .as_ref()
+            .unwrap_unchecked();
+        &*(&**boxed as *const dyn Any as *const V)
+    }
+
+    /// Mutably access value previously inserted in this store, without any check
+    ///
+    /// This is the mutable counterpart of `get_unchecked`. Like `get_mut`, it
+    /// bumps the version of the value and marks it for reindexing.
+    ///
+    /// # Safety
+    ///
+    /// The token must have been created by this store, and its value must not
+    /// have been removed since. Otherwise the behavior is undefined.
+    pub unsafe fn get_unchecked_mut<V: Any + 'static>(&mut self, token: &Token<V>) -> &mut V {
+        self.touch(token.id);
+        if !self.indexes.is_empty() {
+            self.mark_stale(token.id);
+        }
+        let slot = self.values.get_unchecked_mut(token.id);
+        slot.version += 1;
+        let boxed = slot.value.as_mut().unwrap_unchecked();
+        &mut *(&mut **boxed as *mut dyn Any as *mut V)
+    }
+
+    /// Clone every value of type `V` in this store
+    ///
+    /// The clones are returned in slot order.
+    pub fn values_cloned<V: Any + Clone + 'static>(&self) -> Vec<V> {
+        let mut values = Vec::new();
+        for id in 0..self.values.len() {
+            if let Some(&Slot {
+                type_id,
+                value: Some(ref boxed),
+                ..
+            }) = self.values.get(id)
+            {
+                if type_id == TypeId::of::<V>() {
+                    values.push(boxed.downcast_ref::<V>().unwrap().clone());
+                }
+            }
+        }
+        values
+    }
+
+    /// Remove a value previously inserted in this store
+    ///
+    /// Panics if the provided token corresponds to a value that was already
+    /// removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn remove<V: Any + 'static>(&mut self, token: Token<V>) -> V {
+        if self.lookup::<V>(token.id, token.generation).is_none() {
+            self.invalid_access(&token);
+        }
+        let id = token.id;
+        // drop the token first, so that its liveness cell can be recycled
+        drop(token);
+        *self.remove_at(id).downcast().unwrap()
+    }
+
+    /// Drop this store without dropping its values
+    ///
+    /// The memory of the values is leaked, and neither their destructors nor
+    /// their finalizers are run. This is meant for exit paths of a process,
+    /// when tearing down a huge store would only waste time. Tokens are still
+    /// invalidated. The other values held by the store, like the previous
+    /// versions kept by `enable_history`, the values kept by `enable_recycling`
+    /// and the pending messages, are leaked too.
+    pub fn fast_drop(mut self) {
+        for id in 0..self.values.len() {
+            if let Some(ref live) = self.values[id].live {
+                live.set(false);
+            }
+        }
+        mem::forget(mem::replace(&mut self.values, ChunkedVec::new()));
+        mem::forget(mem::take(&mut self.tombstones));
+        mem::forget(mem::take(&mut self.histories));
+        mem::forget(mem::take(&mut self.pools));
+        mem::forget(self.mailboxes.take());
+    }
+
+    /// Invalidate all the tokens of a value, and issue a new one
+    ///
+    /// The value stays in place, but all the existing tokens to it, including
+    /// `token`, become invalid. The returned token is the only way to reach
+    /// it afterwards. It has a new serial, so it does not compare equal to
+    /// the revoked tokens, and the value moves to the end of the insertion
+    /// order.
+    ///
+    /// The links to the value, its scheduled removal, its pending
+    /// `mark_for_removal` and the effects depending on it follow it to the
+    /// new token. Derived values depending on it see it as removed.
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn revoke<V: Any + 'static>(&mut self, token: &Token<V>) -> Token<V> {
+        if self.lookup::<V>(token.id, token.generation).is_none() {
+            self.invalid_access(token);
+        }
+        if !self.bump_generation(token.id) {
+            panic!("Attempted to revoke the tokens of a value in exhausted slot {}.", token.id);
+        }
+        let live = self.fresh_cell();
+        let old = {
+            let slot = &mut self.values[token.id];
+            slot.serial = NEXT_SERIAL.fetch_add(1, Ordering::Relaxed);
+            slot.live.replace(live.clone()).unwrap()
+        };
+        old.set(false);
+        self.recycle_cell(old);
+        let slot = &self.values[token.id];
+        let new = Token {
+            id: token.id,
+            generation: slot.generation,
+            serial: slot.serial,
+            live,
+            provenance: slot.provenance,
+            _type: PhantomData,
+        };
+        self.retarget(&AnyToken::from(token), &AnyToken::from(&new));
+        new
+    }
+
+    // Replaces a revoked token by its successor in the tables referring to
+    // values by token
+    fn retarget(&mut self, old: &AnyToken, new: &AnyToken) {
+        let replace = |token: &mut AnyToken| {
+            if token == old {
+                *token = new.clone();
+            }
+        };
+        self.links.values_mut().flat_map(|links| links.iter_mut()).for_each(|(_, to)| replace(to));
+        self.schedule.values_mut().flat_map(|tokens| tokens.iter_mut()).for_each(replace);
+        self.pending_removals.get_mut().iter_mut().for_each(replace);
+        self.retarget_effects(old, new);
+    }
+
+    /// Remove a value from this store and leak it
+    ///
+    /// The value is never dropped, and stays at the same address for the
+    /// rest of the program. This is meant for values that need to live as
+    /// long as the process, once they have been set up in the store.
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn leak<V: Any + 'static>(&mut self, token: Token<V>) -> &'static mut V {
+        if self.lookup::<V>(token.id, token.generation).is_none() {
+            self.invalid_access(&token);
+        }
+        let id = token.id;
+        drop(token);
+        Box::leak(self.remove_at(id).downcast().unwrap())
+    }
+
+    /// Convert a value to another type, keeping it in the same slot
+    ///
+    /// The value of `token` is removed and converted by `f`, and the result
+    /// is stored in its place. All the tokens of the old value are
+    /// invalidated, and a token to the new value is returned.
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    /// If `f` panics, or if its result is rejected by the validator of `New`,
+    /// the value is removed from the store.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn migrate<Old, New, F>(&mut self, token: &Token<Old>, f: F) -> Token<New>
+    where
+        Old: Any + 'static,
+        New: Any + 'static,
+        F: FnOnce(Old) -> New,
+    {
+        if self.lookup::<Old>(token.id, token.generation).is_none() {
+            self.invalid_access(token);
+        }
+        let provenance = caller_provenance();
+        let id = token.id;
+        if self.values[id].generation == u32::MAX && self.generation_overflow == GenerationOverflow::Retire {
+            panic!("Attempted to migrate a value in exhausted slot {}.", id);
+        }
+        let stable_id = self.clear_stable_id(id);
+        let old = *self.remove_at(id).downcast::<Old>().unwrap();
+        let new = f(old);
+        self.assert_valid(&new);
+        // the slot was just pushed on the free list by `remove_at`
+        self.free.pop_back();
+        let new_token = self.allocate_at::<New>(id, provenance);
+        self.occupy(&new_token, new);
+        if let Some(stable_id) = stable_id {
+            self.assign_stable_id(id, stable_id);
+        }
+        new_token
+    }
+
+    /// Remove every value of type `V` from this store
+    ///
+    /// All their tokens are invalidated, and the removed values are returned
+    /// in slot order, except for the values with a finalizer, which are given
+    /// to it instead.
+    pub fn remove_all_of_type<V: Any + 'static>(&mut self) -> Vec<V> {
+        let mut removed = Vec::new();
+        for id in 0..self.values.len() {
+            let slot = &self.values[id];
+            if slot.type_id == TypeId::of::<V>() && slot.live.is_some() && slot.value.is_some() {
+                if let Some(value) = self.take_or_finalize(id) {
+                    removed.push(*value.downcast().unwrap());
+                }
+            }
+        }
+        removed
+    }
 
-impl<V> Token<V> {
-    /// Check wether this token is still valid
+    /// Consume this store, recovering all its values of type `V`
     ///
-    /// If it is not, trying to use it to access contents
-    /// will panic.
-    pub fn valid(&self) -> bool {
-        self.live.get()
+    /// The values of other types are dropped.
+    pub fn into_values<V: Any + 'static>(mut self) -> Vec<V> {
+        self.remove_all_of_type()
     }
-}
 
-impl<V> Clone for Token<V> {
-    fn clone(&self) -> Token<V> {
-        Token {
-            id: self.id,
-            live: self.live.clone(),
-            _type: PhantomData,
+    /// Consume this store, recovering all its values
+    ///
+    /// The values are returned as boxed `Any` in slot order, along with the
+    /// `TypeId` of their concrete type.
+    pub fn into_inner(mut self) -> Vec<(TypeId, Box<dyn Any>)> {
+        let mut values = Vec::with_capacity(self.len);
+        for id in 0..self.values.len() {
+            let slot = &self.values[id];
+            if slot.live.is_some() && slot.value.is_some() {
+                let type_id = slot.type_id;
+                values.push((type_id, self.remove_at(id)));
+            }
         }
+        values
     }
-}
 
-impl Store {
-    /// Create a new store
-    pub fn new() -> Store {
-        Store { values: Vec::new() }
+    // Removes the value of an occupied slot, invalidating its tokens
+    fn remove_at(&mut self, id: usize) -> Box<dyn Any> {
+        self.remove_slot(id, true)
     }
 
-    /// Insert a new value in this store
-    ///
-    /// Returns a clonable token that you can later use to access this
-    /// value.
-    pub fn insert<V: Any + 'static>(&mut self, value: V) -> Token<V> {
-        let boxed = Box::new(value) as Box<Any>;
-        let live = Rc::new(Cell::new(true));
-        {
-            // artificial scope to make the borrow checker happy
-            let empty_slot = self.values
-                .iter_mut()
-                .enumerate()
-                .find(|&(_, ref s)| s.is_none());
-            if let Some((id, slot)) = empty_slot {
-                *slot = Some((boxed, live.clone()));
-                return Token {
-                    id: id,
-                    live: live,
-                    _type: PhantomData,
-                };
+    // Removes the value of a slot, reporting it to the listeners if `notify`
+    fn remove_slot(&mut self, id: usize, notify: bool) -> Box<dyn Any> {
+        let token = self.slot_token(id).unwrap();
+        let reusable = self.bump_generation(id);
+        let (boxed, live) = {
+            let slot = &mut self.values[id];
+            slot.finalizer = None;
+            (slot.value.take().unwrap().into_box(), slot.live.take().unwrap())
+        };
+        let type_id = self.values[id].type_id;
+        self.count_quota(type_id, false);
+        if reusable {
+            self.free.push_back(id);
+        } else {
+            self.retired.insert(id);
+        }
+        self.len -= 1;
+        self.clear_mailbox(id);
+        self.clear_links(id);
+        self.clear_history(id);
+        if let Some(ref mut timestamps) = self.timestamps {
+            timestamps.remove(&id);
+        }
+        if !self.indexes.is_empty() {
+            self.index_remove(id);
+        }
+        self.clear_stable_id(id);
+        if let Some(ref mut addresses) = self.addresses {
+            if let Some(address) = value_address(&*boxed) {
+                addresses.remove(&address);
             }
         }
-        self.values.push(Some((boxed, live.clone())));
-        Token {
-            id: self.values.len() - 1,
-            live: live,
-            _type: PhantomData,
+        live.set(false);
+        if notify {
+            self.emit(StoreEvent::Removed(token));
         }
+        self.recycle_cell(live);
+        boxed
     }
 
-    /// Access value previously inserted in this store
+    /// List the values currently in this store, with where they were inserted
     ///
-    /// Panics if the provided token corresponds to a value that was removed.
-    pub fn get<V: Any + 'static>(&self, token: &Token<V>) -> &V {
-        if !token.live.get() {
-            panic!("Attempted to access a state value that was already removed!");
+    /// This is useful to track down values that were inserted and never
+    /// removed. Only available with the `provenance` cargo feature.
+    #[cfg(feature = "provenance")]
+    pub fn live_insertions(&self) -> Vec<(AnyToken, &'static ::std::panic::Location<'static>)> {
+        self.iter()
+            .map(|(token, _)| {
+                let provenance = token.provenance;
+                (token, provenance)
+            })
+            .collect()
+    }
+
+    // The token of the value stored in this slot, if any
+    fn slot_token(&self, id: usize) -> Option<AnyToken> {
+        match self.values.get(id) {
+            Some(&Slot {
+                generation,
+                live: Some(ref live),
+                value: Some(_),
+                serial,
+                provenance,
+                ..
+            }) => Some(AnyToken {
+                id,
+                generation,
+                serial,
+                live: live.clone(),
+                provenance,
+            }),
+            _ => None,
         }
-        self.values[token.id]
-            .as_ref()
-            .and_then(|t| t.0.downcast_ref::<V>())
-            .unwrap()
     }
 
-    /// Mutably access value previously inserted in this store
-    ///
-    /// Panics if the provided token corresponds to a value that was removed.
-    pub fn get_mut<V: Any + 'static>(&mut self, token: &Token<V>) -> &mut V {
-        if !token.live.get() {
-            panic!("Attempted to access a state value that was already removed!");
+    // A liveness cell set to live, taken from the pool if possible
+    fn fresh_cell(&mut self) -> LiveCell {
+        match self.cell_pool.pop() {
+            Some(live) => {
+                live.set(true);
+                live
+            }
+            None => LiveCell::new(true),
         }
-        self.values[token.id]
-            .as_mut()
-            .and_then(|t| t.0.downcast_mut::<V>())
-            .unwrap()
     }
 
-    /// Remove a value previously inserted in this store
-    ///
-    /// Panics if the provided token corresponds to a value that was already
-    /// removed.
-    pub fn remove<V: Any + 'static>(&mut self, token: Token<V>) -> V {
-        if !token.live.get() {
-            panic!("Attempted to remove a state value that was already removed!");
+    fn recycle_cell(&mut self, live: LiveCell) {
+        // the cell can only be reused if no token can observe it anymore
+        if live.is_unique() {
+            self.cell_pool.push(live);
         }
-        let (boxed, live) = self.values[token.id].take().unwrap();
-        live.set(false);
-        *boxed.downcast().unwrap()
+    }
+
+    /// Replace a value previously inserted in this store
+    ///
+    /// The new value takes the place of the old one, which is returned. The
+    /// tokens of this value remain valid.
+    ///
+    /// Panics if the provided token corresponds to a value that was removed,
+    /// or if the new value is rejected by the validator of `V`.
+    pub fn replace<V: Any + 'static>(&mut self, token: &Token<V>, value: V) -> V {
+        self.assert_valid(&value);
+        let old = ::std::mem::replace(self.get_mut(token), value);
+        self.emit(StoreEvent::Replaced(AnyToken::from(token)));
+        old
     }
 
     /// Create a sub-scope with access to a value
@@ -209,6 +1617,10 @@ impl Store {
     /// The `StoreProxy` provides the same access methods as the `Store`,
     /// including `with_value`, allowing you to create nested sub-scopes
     /// accessing multiple store values at the same time.
+    ///
+    /// If the closure panics, the value is marked as poisoned: it may have
+    /// been left in an inconsistent state, so accessing it again will panic
+    /// until `unpoison` is called.
     pub fn with_value<V: Any + 'static, T, F>(&mut self, token: &Token<V>, f: F) -> T
     where
         F: FnOnce(&mut StoreProxy, &mut V) -> T,
@@ -268,8 +1680,9 @@ impl<'store> StoreProxy<'store> {
     ///
     /// Returns a clonable token that you can later use to access this
     /// value.
+    #[cfg_attr(feature = "provenance", track_caller)]
     pub fn insert<V: Any + 'static>(&mut self, value: V) -> Token<V> {
-        self.store.insert(value)
+        self.store.insert_sparing(value, &self.borrowed)
     }
 
     /// Access value previously inserted in the proxified store
@@ -305,6 +1718,17 @@ impl<'store> StoreProxy<'store> {
         self.store.remove(token)
     }
 
+    /// Replace a value previously inserted in the proxified store
+    ///
+    /// Panics if the provided token corresponds to a value that was removed, or
+    /// if this value is already borrowed.
+    pub fn replace<V: Any + 'static>(&mut self, token: &Token<V>, value: V) -> V {
+        if self.borrowed.contains(&token.id) {
+            panic!("Attempted to borrow twice the same value from the Store!");
+        }
+        self.store.replace(token, value)
+    }
+
     /// Create a sub-scope with access to a value
     ///
     /// Panics if the provided token corresponds to a value that was removed, or
@@ -328,7 +1752,14 @@ impl<'store> StoreProxy<'store> {
                 Cow::Owned(my_borrowed)
             },
         };
-        f(&mut deeper_proxy, value)
+        // poison the value if the closure panics while it is borrowed
+        match panic::catch_unwind(AssertUnwindSafe(|| f(&mut deeper_proxy, value))) {
+            Ok(result) => result,
+            Err(payload) => {
+                self.store.values[token.id].poisoned = true;
+                panic::resume_unwind(payload)
+            }
+        }
     }
 }
 
@@ -375,6 +1806,17 @@ mod tests {
         let _v = store.get_mut(&token);
     }
 
+    #[test]
+    #[should_panic(expected = "slot 0, generation 0, type i32: this slot now holds generation 1 of type &str")]
+    fn access_removed_diagnostics() {
+        let mut store = Store::new();
+        store.set_generation_salting(false);
+        let token = store.insert(42);
+        store.remove(token.clone());
+        store.insert("I like trains");
+        let _v = store.get(&token);
+    }
+
     #[test]
     #[should_panic]
     fn no_double_remove() {
@@ -469,6 +1911,462 @@ mod tests {
         assert_eq!(*store.get(&token2), 42);
     }
 
+    #[test]
+    fn alive_and_contains() {
+        let mut store = Store::new();
+        let token = store.insert(42);
+        let any_token = AnyToken::from(&token);
+        assert!(store.is_alive(&any_token));
+        assert!(store.contains(&token));
+        store.remove(token.clone());
+        assert!(!store.is_alive(&any_token));
+        assert!(!store.contains(&token));
+        // a token from another store is never recognized
+        let mut other = Store::new();
+        let other_token = other.insert(42);
+        let _ = store.insert(42);
+        assert!(!store.contains(&other_token));
+    }
+
+    #[test]
+    fn raw_parts_roundtrip() {
+        let mut store = Store::new();
+        let token = store.insert(42);
+        let (index, generation) = token.clone().into_raw_parts();
+        let rebuilt = store.token_from_raw_parts::<i32>(index, generation).unwrap();
+        assert_eq!(*store.get(&rebuilt), 42);
+        // wrong type
+        assert!(store.token_from_raw_parts::<u8>(index, generation).is_none());
+        // stale generation after slot reuse
+        store.remove(token);
+        let _ = store.insert(12);
+        assert!(store.token_from_raw_parts::<i32>(index, generation).is_none());
+        assert!(store.token_from_raw_parts::<i32>(index, generation + 1).is_some());
+    }
+
+    #[test]
+    fn handles_roundtrip() {
+        let mut store = Store::new();
+        // keep the handle small enough for fractional f64 handles to exist
+        store.set_generation_salting(false);
+        let token = store.insert(42);
+        let handle = token.to_handle();
+        let f64_handle = token.to_f64_handle();
+        assert_eq!(*store.get(&store.token_from_handle::<i32>(handle).unwrap()), 42);
+        assert_eq!(*store.get(&store.token_from_f64_handle::<i32>(f64_handle).unwrap()), 42);
+        assert!(store.token_from_f64_handle::<i32>(f64_handle + 0.5).is_none());
+        assert!(store.token_from_f64_handle::<i32>(-1.0).is_none());
+        store.remove(token);
+        let _ = store.insert(12);
+        assert!(store.token_from_handle::<i32>(handle).is_none());
+        assert!(store.token_from_f64_handle::<i32>(f64_handle).is_none());
+    }
+
+    #[test]
+    fn replace() {
+        let mut store = Store::new();
+        let token = store.insert(42);
+        assert_eq!(store.replace(&token, 12), 42);
+        assert_eq!(*store.get(&token), 12);
+        assert!(token.is_valid());
+    }
+
+    #[test]
+    fn event_stream() {
+        let mut store = Store::new();
+        let events = store.events();
+        let token = store.insert(42);
+        store.replace(&token, 12);
+        store.remove(token);
+        match events.try_recv() {
+            Ok(StoreEvent::Inserted(_)) => {}
+            other => panic!("Unexpected event: {:?}", other),
+        }
+        match events.try_recv() {
+            Ok(StoreEvent::Replaced(_)) => {}
+            other => panic!("Unexpected event: {:?}", other),
+        }
+        match events.try_recv() {
+            Ok(StoreEvent::Removed(token)) => assert!(!token.is_valid()),
+            other => panic!("Unexpected event: {:?}", other),
+        }
+        assert!(events.try_recv().is_err());
+        drop(events);
+        store.insert(42);
+        assert!(store.listeners.is_empty());
+    }
+
+    #[test]
+    fn unchecked_access() {
+        let mut store = Store::new();
+        let _ = store.insert("I like trains");
+        let token = store.insert(42);
+        assert!(store.contains(&token));
+        unsafe {
+            *store.get_unchecked_mut(&token) += 1;
+            assert_eq!(*store.get_unchecked(&token), 43);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn no_access_mismatched_type() {
+        let mut store = Store::new();
+        let mut other = Store::new();
+        let _ = store.insert(42u8);
+        let token = other.insert(String::from("I like trains"));
+        let _v = store.get(&token);
+    }
+
+    #[test]
+    fn liveness_cells_reuse() {
+        let mut store = Store::new();
+        let token = store.insert(42);
+        store.remove(token);
+        assert_eq!(store.cell_pool.len(), 1);
+        let token = store.insert(42);
+        assert!(store.cell_pool.is_empty());
+        // a cell still observed by a token is not reused
+        store.remove(token.clone());
+        assert!(store.cell_pool.is_empty());
+        let token2 = store.insert(12);
+        assert!(!token.is_valid());
+        assert!(token2.is_valid());
+    }
+
+    #[test]
+    fn reserve_and_fill() {
+        let mut store = Store::new();
+        let tokens = store.reserve_tokens::<i32>(3);
+        assert!(tokens.iter().all(|t| t.is_valid() && !store.contains(t)));
+        for (i, token) in tokens.iter().enumerate() {
+            store.fill(token, i as i32);
+        }
+        assert_eq!(*store.get(&tokens[2]), 2);
+        assert!(store.contains(&tokens[0]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn no_access_reserved() {
+        let mut store = Store::new();
+        let tokens = store.reserve_tokens::<i32>(1);
+        let _v = store.get(&tokens[0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn no_double_fill() {
+        let mut store = Store::new();
+        let token = store.insert(42);
+        store.fill(&token, 12);
+    }
+
+    #[cfg(feature = "provenance")]
+    #[test]
+    fn insertion_provenance() {
+        let mut store = Store::new();
+        let line = line!() + 1;
+        let token = store.insert(42);
+        assert_eq!(token.inserted_at().line(), line);
+        assert_eq!(token.inserted_at().file(), file!());
+        let insertions = store.live_insertions();
+        assert_eq!(insertions.len(), 1);
+        assert_eq!(insertions[0].1.line(), line);
+    }
+
+    #[test]
+    fn iterate_in_insertion_order() {
+        let mut store = Store::new();
+        let token1 = store.insert(1);
+        let token2 = store.insert(2);
+        let _ = store.insert(3);
+        store.remove(token1);
+        let _ = store.insert(4);
+        store.remove(token2);
+        let _ = store.insert(5);
+        let in_slots = store
+            .iter()
+            .map(|(_, v)| *v.downcast_ref::<i32>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(in_slots, vec![4, 5, 3]);
+        let ordered = store
+            .iter_ordered()
+            .map(|(_, v)| *v.downcast_ref::<i32>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(ordered, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn creation_order() {
+        let mut store = Store::new();
+        let token1 = store.insert(1);
+        let token2 = store.insert(2);
+        store.remove(token1.clone());
+        let token3 = store.insert(3);
+        assert!(token1.serial() < token2.serial());
+        assert!(token2 < token3);
+        assert_eq!(token2, token2.clone());
+        let mut tokens = [
+            AnyToken::from(&token3),
+            AnyToken::from(&token1),
+            AnyToken::from(&token2),
+        ];
+        tokens.sort();
+        assert_eq!(
+            tokens.iter().map(|t| t.serial()).collect::<Vec<_>>(),
+            vec![token1.serial(), token2.serial(), token3.serial()]
+        );
+    }
+
+    #[test]
+    fn remove_all_of_type() {
+        let mut store = Store::new();
+        let token1 = store.insert(1);
+        let token2 = store.insert("I like trains");
+        let token3 = store.insert(3);
+        assert_eq!(store.remove_all_of_type::<i32>(), vec![1, 3]);
+        assert!(!token1.is_valid());
+        assert!(!token3.is_valid());
+        assert!(token2.is_valid());
+        assert_eq!(store.len(), 1);
+        assert!(store.remove_all_of_type::<i32>().is_empty());
+    }
+
+    #[test]
+    fn consuming_extraction() {
+        let mut store = Store::new();
+        let token = store.insert(1);
+        store.insert("I like trains");
+        store.insert(3);
+        let mut other = Store::new();
+        other.insert(1);
+        other.insert("I like trains");
+        assert_eq!(store.into_values::<i32>(), vec![1, 3]);
+        assert!(!token.is_valid());
+        let values = other.into_inner();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0].0, TypeId::of::<i32>());
+        assert_eq!(values[1].1.downcast_ref::<&str>(), Some(&"I like trains"));
+    }
+
+    #[test]
+    fn insert_iter() {
+        let mut store = Store::new();
+        let tokens = store.insert_iter(vec![1, 2, 3]);
+        assert_eq!(tokens.len(), 3);
+        for (i, token) in tokens.iter().enumerate() {
+            assert_eq!(*store.get(token), i as i32 + 1);
+        }
+        store.extend(vec!["a", "b"]);
+        assert_eq!(store.len(), 5);
+    }
+
+    #[test]
+    fn values_cloned() {
+        let mut store = Store::new();
+        store.insert(1);
+        let token = store.insert(2);
+        store.insert("I like trains");
+        store.insert(3);
+        store.remove(token);
+        assert_eq!(store.values_cloned::<i32>(), vec![1, 3]);
+        assert_eq!(store.values_cloned::<&str>(), vec!["I like trains"]);
+        assert!(store.values_cloned::<u8>().is_empty());
+    }
+
+    #[test]
+    fn get_cloned() {
+        let mut store = Store::new();
+        let token = store.insert(String::from("I like trains"));
+        let value = store.get_cloned(&token);
+        store.get_mut(&token).push('!');
+        assert_eq!(value, "I like trains");
+        assert_eq!(store.get(&token), "I like trains!");
+    }
+
+    #[test]
+    fn shared_values() {
+        let mut store = Store::new();
+        let token = store.insert_shared(vec![1, 2, 3]);
+        let shared = store.get_shared(&token);
+        store.insert(42);
+        assert_eq!(*shared, vec![1, 2, 3]);
+        assert_eq!(Rc::strong_count(&shared), 2);
+        store.remove(token);
+        assert_eq!(Rc::strong_count(&shared), 1);
+    }
+
+    #[test]
+    fn migrate() {
+        let mut store = Store::new();
+        let token = store.insert(42u32);
+        let other = store.insert(0u32);
+        let new_token = store.migrate(&token, |v| format!("{}", v));
+        assert!(!token.is_valid());
+        assert!(other.is_valid());
+        assert_eq!(new_token.id, token.id);
+        assert_eq!(store.get(&new_token), "42");
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn type_of() {
+        let mut store = Store::new();
+        let token = AnyToken::from(store.insert(42u32));
+        assert_eq!(store.type_id_of(&token), Some(TypeId::of::<u32>()));
+        assert_eq!(store.type_name_of(&token), Some("u32"));
+        let token = AnyToken::from(store.insert(String::new()));
+        assert_eq!(store.type_name_of(&token), Some(type_name::<String>()));
+        store.remove_all_of_type::<String>();
+        assert_eq!(store.type_id_of(&token), None);
+    }
+
+    #[test]
+    fn insert_with_id() {
+        let mut store = Store::new();
+        let token = store.insert_with_id(3, 42).unwrap();
+        assert_eq!(token.id, 3);
+        assert_eq!(store.insert_with_id(3, 0), Err(0));
+        assert_eq!(*store.get(&token), 42);
+        store.set_slot_reuse(false);
+        let token = store.insert(1);
+        assert_eq!(token.id, 4);
+        store.remove(token);
+        assert_eq!(store.insert(2).id, 5);
+        store.set_slot_reuse(true);
+        assert_eq!(store.insert(3).id, 4);
+        assert!(store.debug_validate().is_ok());
+    }
+
+    #[test]
+    fn drop_order() {
+        struct Logged(u32, Rc<RefCell<Vec<u32>>>);
+        impl Drop for Logged {
+            fn drop(&mut self) {
+                self.1.borrow_mut().push(self.0);
+            }
+        }
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut store = Store::new();
+        let first = store.insert(Logged(0, log.clone()));
+        store.insert(Logged(1, log.clone()));
+        store.remove(first);
+        // reuses the slot of the first value
+        store.insert(Logged(2, log.clone()));
+        store.insert(Logged(3, log.clone()));
+        drop(store);
+        assert_eq!(*log.borrow(), vec![0, 3, 2, 1]);
+    }
+
+    #[test]
+    fn leak() {
+        let mut store = Store::new();
+        let token = store.insert(42);
+        let address = store.get(&token) as *const i32;
+        let leaked: &'static mut i32 = store.leak(token.clone());
+        assert_eq!(leaked as *const i32, address);
+        assert!(!token.is_valid());
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn drop_invalidates_tokens() {
+        let mut store = Store::new();
+        let token = store.insert(42);
+        let reserved = store.reserve_tokens::<i32>(1).pop().unwrap();
+        drop(store);
+        assert!(!token.is_valid());
+        assert!(!reserved.is_valid());
+    }
+
+    #[test]
+    fn send_tokens() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+        let mut store = Store::new();
+        let token = store.insert(42);
+        assert_send_sync(&token);
+        assert_send_sync(&AnyToken::from(&token));
+        let token = ::std::thread::spawn(move || token).join().unwrap();
+        assert_eq!(*store.get(&token), 42);
+        store.remove(token.clone());
+        assert!(!::std::thread::spawn(move || token.is_valid()).join().unwrap());
+    }
+
+    #[test]
+    fn revoke() {
+        let mut store = Store::new();
+        let token = store.insert(42);
+        let clone = token.clone();
+        let other = store.insert(0);
+        store.link(&other, "to", &token);
+        let new = store.revoke(&token);
+        assert!(!token.is_valid() && !clone.is_valid());
+        assert!(!store.contains(&clone));
+        assert_ne!(AnyToken::from(&token), AnyToken::from(&new));
+        assert_eq!(store.linked(&other, "to"), vec![AnyToken::from(&new)]);
+        assert_eq!(*store.get(&new), 42);
+        assert_eq!(store.len(), 2);
+        assert!(store.debug_validate().is_ok());
+    }
+
+    #[test]
+    fn zero_sized_values() {
+        struct Marker;
+        let mut store = Store::new();
+        let first = store.insert(Marker);
+        let second = store.insert(Marker);
+        assert_ne!(first, second);
+        store.remove(first.clone());
+        assert!(!store.contains(&first));
+        assert!(store.contains(&second));
+        let third = store.insert(Marker);
+        assert!(!store.contains(&first));
+        assert!(store.contains(&third));
+    }
+
+    #[test]
+    fn over_aligned_values() {
+        #[repr(align(32))]
+        struct Simd([f32; 8]);
+        let mut store = Store::new();
+        store.insert(1u8);
+        let tokens = (0..16).map(|_| store.insert(Simd([0.0; 8]))).collect::<Vec<_>>();
+        for token in &tokens {
+            assert_eq!(store.get(token) as *const Simd as usize % 32, 0);
+            assert_eq!(store.get(token).0[0], 0.0);
+        }
+    }
+
+    #[test]
+    fn fast_drop() {
+        struct Loud(Rc<::std::cell::Cell<bool>>);
+        impl Drop for Loud {
+            fn drop(&mut self) {
+                self.0.set(true);
+            }
+        }
+        let dropped = Rc::new(::std::cell::Cell::new(false));
+        let mut store = Store::new();
+        let token = store.insert(Loud(dropped.clone()));
+        store.fast_drop();
+        assert!(!token.is_valid());
+        assert!(!dropped.get());
+    }
+
+    #[test]
+    fn fifo_reuse_order() {
+        let mut store = Store::new();
+        store.set_reuse_order(ReuseOrder::Fifo);
+        let a = store.insert(1);
+        let b = store.insert(2);
+        let (a_id, b_id) = (a.id, b.id);
+        store.remove(a);
+        store.remove(b);
+        assert_eq!(store.insert(3).id, a_id);
+        assert_eq!(store.insert(4).id, b_id);
+    }
+
     #[test]
     fn token_validity() {
         let mut store = Store::new();
@@ -477,4 +2375,16 @@ mod tests {
         store.remove(token.clone());
         assert!(!token.valid());
     }
+
+    #[test]
+    fn token_is_valid() {
+        let mut store = Store::new();
+        let token = store.insert(42);
+        let any_token = AnyToken::from(&token);
+        assert!(token.is_valid());
+        assert!(any_token.is_valid());
+        store.remove(token.clone());
+        assert!(!token.is_valid());
+        assert!(!any_token.is_valid());
+    }
 }
@@ -0,0 +1,249 @@
+use super::{AnyToken, Store, Token};
+use std::alloc::{self, Layout};
+use std::any::{type_name, Any, TypeId};
+use std::collections::TryReserveError;
+use std::ptr;
+
+// The maximum number of values of a type, and how many there are
+pub(crate) struct Quota {
+    max: usize,
+    count: usize,
+}
+
+// Called with the name of the type of the values rejected by a quota
+pub(crate) type QuotaHandler = Box<dyn FnMut(&'static str)>;
+
+// Boxes a value, giving it back if the allocation fails
+fn try_box<V: Any + 'static>(value: V) -> Result<Box<dyn Any>, V> {
+    let layout = Layout::new::<V>();
+    if layout.size() == 0 {
+        return Ok(Box::new(value));
+    }
+    unsafe {
+        let raw = alloc::alloc(layout) as *mut V;
+        if raw.is_null() {
+            return Err(value);
+        }
+        ptr::write(raw, value);
+        // the memory was allocated with the layout `Box` uses for V
+        Ok(Box::from_raw(raw))
+    }
+}
+
+impl Store {
+    /// Set the maximum number of values this store can hold
+    ///
+    /// Slots reserved with `reserve_tokens` count as values. Once the store
+    /// is full, `try_insert` fails and the other insertion methods panic.
+    /// Passing `None` removes the limit, which is the default.
+    ///
+    /// Lowering the limit below the current number of values does not remove
+    /// any value, but prevents further insertions.
+    pub fn set_max_len(&mut self, max: Option<usize>) {
+        self.max_len = max;
+    }
+
+    /// Set the maximum number of values of type `V` this store can hold
+    ///
+    /// Slots reserved with `reserve_tokens` count as values. Once the quota
+    /// is reached, `try_insert` fails for values of this type and the other
+    /// insertion methods panic. Passing `None` removes the quota.
+    pub fn set_quota<V: Any + 'static>(&mut self, max: Option<usize>) {
+        let type_id = TypeId::of::<V>();
+        match max {
+            Some(max) => {
+                let count = (0..self.values.len())
+                    .filter(|&id| {
+                        self.values[id].type_id == type_id
+                            && self.values[id].live.is_some()
+                            && !self.tombstones.contains_key(&id)
+                    })
+                    .count();
+                self.quotas.insert(type_id, Quota { max, count });
+            }
+            None => {
+                self.quotas.remove(&type_id);
+            }
+        }
+    }
+
+    /// Set a function to be called when a quota is exceeded
+    ///
+    /// It is given the name of the type of the rejected value, and is called
+    /// before the insertion fails.
+    pub fn set_quota_handler<F: FnMut(&'static str) + 'static>(&mut self, handler: F) {
+        self.quota_handler = Some(Box::new(handler));
+    }
+
+    /// Insert a new value in this store, unless it is full
+    ///
+    /// If the store holds as many values as allowed by `set_max_len`, and
+    /// none could be evicted, if the quota of `V` set by `set_quota` is
+    /// reached, if the store is sealed, or if the value is rejected by the
+    /// validator of `V`, the value is given back as an error. It is also
+    /// given back if the memory for its slot or for the value itself could
+    /// not be allocated. Nothing is evicted when the insertion fails.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn try_insert<V: Any + 'static>(&mut self, value: V) -> Result<Token<V>, V> {
+        if self.sealed || self.check_value(&value).is_err() {
+            return Err(value);
+        }
+        if self.is_full_after(self.pending_evictions(&[]))
+            || self.quota_reached(TypeId::of::<V>(), type_name::<V>())
+        {
+            return Err(value);
+        }
+        if self.try_reserve(1).is_err() {
+            return Err(value);
+        }
+        let boxed = try_box(value)?;
+        self.make_room(&[]);
+        let token = self.allocate::<V>();
+        self.occupy_any(AnyToken::from(&token), boxed);
+        Ok(token)
+    }
+
+    /// Reserve memory for `additional` more values, reporting failures
+    ///
+    /// Once this succeeds, that many insertions do not need to grow the
+    /// slot table or the free list. Allocation failures are reported as an
+    /// error instead of aborting the process.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let reusable = if self.reuse_slots { self.free.len() } else { 0 };
+        let needed = additional.saturating_sub(reusable);
+        self.values.try_reserve(needed)?;
+        // every slot may end up on the free list
+        let slots = self.values.len().saturating_add(needed);
+        self.free.try_reserve(slots.saturating_sub(self.free.len()))
+    }
+
+    // Checks wether the maximum number of values was reached
+    pub(crate) fn is_full(&self) -> bool {
+        self.is_full_after(0)
+    }
+
+    // Checks wether the maximum number of values would still be reached once
+    // `evicted` values are removed
+    pub(crate) fn is_full_after(&self, evicted: usize) -> bool {
+        match self.max_len {
+            Some(max) => self.values.len() - self.free.len() - self.retired.len() - evicted >= max,
+            None => false,
+        }
+    }
+
+    // The number of values `make_room` would evict before an insertion
+    pub(crate) fn pending_evictions(&self, spared: &[usize]) -> usize {
+        match self.eviction_limit {
+            Some(limit) => (self.len + 1).saturating_sub(limit).min(self.len - spared.len()),
+            None => 0,
+        }
+    }
+
+    // Checks wether the quota of a type was reached, notifying the handler
+    pub(crate) fn quota_reached(&mut self, type_id: TypeId, type_name: &'static str) -> bool {
+        match self.quotas.get(&type_id) {
+            Some(quota) if quota.count >= quota.max => {}
+            _ => return false,
+        }
+        if let Some(ref mut handler) = self.quota_handler {
+            handler(type_name);
+        }
+        true
+    }
+
+    // Updates the quota of a type when a value is added or removed
+    pub(crate) fn count_quota(&mut self, type_id: TypeId, added: bool) {
+        if let Some(quota) = self.quotas.get_mut(&type_id) {
+            if added {
+                quota.count += 1;
+            } else {
+                quota.count -= 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounded_store() {
+        let mut store = Store::new();
+        store.set_max_len(Some(2));
+        let token = store.try_insert(1).unwrap();
+        store.reserve_tokens::<i32>(1);
+        assert_eq!(store.try_insert(2), Err(2));
+        assert_eq!(store.insert_with_id(5, 2), Err(2));
+        store.remove(token);
+        assert!(store.try_insert(3).is_ok());
+        store.set_max_len(None);
+        assert!(store.try_insert(4).is_ok());
+    }
+
+    #[test]
+    fn fallible_reservation() {
+        let mut store = Store::new();
+        assert!(store.try_reserve(10_000).is_ok());
+        assert!(store.try_reserve(usize::MAX).is_err());
+        let token = store.try_insert(String::from("I like trains")).unwrap();
+        assert_eq!(store.get(&token), "I like trains");
+        assert!(store.try_insert(()).is_ok());
+        assert!(store.debug_validate().is_ok());
+    }
+
+    #[test]
+    fn eviction_makes_room() {
+        let mut store = Store::new();
+        store.set_max_len(Some(1));
+        store.set_eviction_limit(Some(1));
+        store.insert(1);
+        assert!(store.try_insert(2).is_ok());
+        assert_eq!(store.values_cloned::<i32>(), vec![2]);
+    }
+
+    #[test]
+    fn quotas() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        let rejected = Rc::new(RefCell::new(Vec::new()));
+        let mut store = Store::new();
+        let token = store.insert(1);
+        store.insert(2);
+        {
+            let rejected = rejected.clone();
+            store.set_quota_handler(move |name| rejected.borrow_mut().push(name));
+        }
+        store.set_quota::<i32>(Some(2));
+        assert_eq!(store.try_insert(3), Err(3));
+        assert!(store.try_insert("I like trains").is_ok());
+        store.remove(token);
+        assert!(store.try_insert(3).is_ok());
+        assert_eq!(store.try_insert(4), Err(4));
+        assert_eq!(*rejected.borrow(), vec!["i32", "i32"]);
+        store.set_quota::<i32>(None);
+        assert!(store.try_insert(4).is_ok());
+    }
+
+    #[test]
+    fn no_eviction_on_failure() {
+        let mut store = Store::new();
+        store.set_eviction_limit(Some(1));
+        let kept = store.insert("kept");
+        store.set_quota::<i32>(Some(0));
+        assert_eq!(store.try_insert(1), Err(1));
+        assert_eq!(store.insert_with_id(4, 2), Err(2));
+        assert!(kept.is_valid());
+        store.set_quota::<i32>(None);
+        assert!(store.try_insert(3).is_ok());
+        assert!(!kept.is_valid());
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_in_full_store() {
+        let mut store = Store::new();
+        store.set_max_len(Some(0));
+        store.insert(1);
+    }
+}
@@ -0,0 +1,161 @@
+use super::{AnyToken, Key, Store};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+// The outgoing links of each slot, with their names
+pub(crate) type Links = HashMap<usize, Vec<(String, AnyToken)>>;
+
+impl Store {
+    /// Add a named link from one value to another
+    ///
+    /// Links are directed, and a value can have any number of links with the
+    /// same name. They are automatically removed when either of their values
+    /// is removed. Adding a link that already exists does nothing.
+    ///
+    /// Panics if one of the values was removed from this store.
+    pub fn link<A: Key, B: Key>(&mut self, from: &A, name: &str, to: &B) {
+        let (from, to) = (from.any_token(), to.any_token());
+        if !self.is_alive(&from) || !self.is_alive(&to) {
+            panic!("Attempted to link a value that was already removed!");
+        }
+        let links = self.links.entry(from.id).or_default();
+        if !links.iter().any(|(n, t)| n == name && *t == to) {
+            links.push((name.to_owned(), to));
+        }
+    }
+
+    /// Remove a named link from one value to another
+    ///
+    /// Returns wether the link existed.
+    pub fn unlink<A: Key, B: Key>(&mut self, from: &A, name: &str, to: &B) -> bool {
+        let (from, to) = (from.any_token(), to.any_token());
+        if !self.is_alive(&from) {
+            return false;
+        }
+        match self.links.get_mut(&from.id) {
+            Some(links) => {
+                let before = links.len();
+                links.retain(|(n, t)| n != name || *t != to);
+                before != links.len()
+            }
+            None => false,
+        }
+    }
+
+    /// The values a value links to with a given name
+    ///
+    /// They are listed in the order the links were added. Returns an empty
+    /// list if the value was removed.
+    pub fn linked<K: Key>(&self, from: &K, name: &str) -> Vec<AnyToken> {
+        let from = from.any_token();
+        if !self.is_alive(&from) {
+            return Vec::new();
+        }
+        match self.links.get(&from.id) {
+            Some(links) => links
+                .iter()
+                .filter(|&(n, _)| n == name)
+                .map(|(_, to)| to.clone())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// The values linking to a value with a given name
+    ///
+    /// Returns an empty list if the value was removed.
+    pub fn linked_from<K: Key>(&self, to: &K, name: &str) -> Vec<AnyToken> {
+        let to = to.any_token();
+        if !self.is_alive(&to) {
+            return Vec::new();
+        }
+        let mut sources = self
+            .links
+            .iter()
+            .filter(|&(_, links)| links.iter().any(|(n, t)| n == name && *t == to))
+            .filter_map(|(&id, _)| self.slot_token(id))
+            .collect::<Vec<_>>();
+        sources.sort();
+        sources
+    }
+
+    /// Describe the values of this store and their links as a Graphviz graph
+    ///
+    /// Every value is a node labeled with its slot index and the name of its
+    /// type, and every link is an edge labeled with its name. The output can
+    /// be rendered with `dot`.
+    pub fn export_dot(&self) -> String {
+        let mut dot = String::from("digraph store {\n");
+        for id in 0..self.values.len() {
+            if self.slot_token(id).is_some() {
+                let label = format!("#{} {}", id, self.values[id].type_name);
+                writeln!(dot, "    n{} [label=\"{}\"];", id, escape(&label)).unwrap();
+            }
+        }
+        let mut sources = self.links.keys().cloned().collect::<Vec<_>>();
+        sources.sort();
+        for from in sources {
+            for (name, to) in &self.links[&from] {
+                writeln!(dot, "    n{} -> n{} [label=\"{}\"];", from, to.id, escape(name)).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    // Removes the links from and to the value of a slot
+    pub(crate) fn clear_links(&mut self, id: usize) {
+        if self.links.is_empty() {
+            return;
+        }
+        self.links.remove(&id);
+        self.links.retain(|_, links| {
+            links.retain(|(_, to)| to.id != id);
+            !links.is_empty()
+        });
+    }
+}
+
+// Escapes a string to be used between quotes in a Graphviz file
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links() {
+        let mut store = Store::new();
+        let a = store.insert("a");
+        let b = store.insert("b");
+        let c = store.insert("c");
+        store.link(&a, "outputs", &b);
+        store.link(&a, "outputs", &c);
+        store.link(&a, "outputs", &c);
+        store.link(&c, "outputs", &b);
+        store.link(&a, "focus", &c);
+        assert_eq!(store.linked(&a, "outputs"), vec![AnyToken::from(&b), AnyToken::from(&c)]);
+        assert_eq!(store.linked(&a, "focus"), vec![AnyToken::from(&c)]);
+        assert_eq!(store.linked_from(&b, "outputs"), vec![AnyToken::from(&a), AnyToken::from(&c)]);
+        assert!(store.unlink(&a, "focus", &c));
+        assert!(!store.unlink(&a, "focus", &c));
+        store.remove(b);
+        assert_eq!(store.linked(&a, "outputs"), vec![AnyToken::from(&c)]);
+        assert!(store.linked(&c, "outputs").is_empty());
+    }
+
+    #[test]
+    fn export_dot() {
+        let mut store = Store::new();
+        let a = store.insert(1u8);
+        let b = store.insert("b");
+        store.link(&a, "needs \"b\"", &b);
+        assert_eq!(
+            store.export_dot(),
+            "digraph store {\n    n0 [label=\"#0 u8\"];\n    n1 [label=\"#1 &str\"];\n    \
+             n0 -> n1 [label=\"needs \\\"b\\\"\"];\n}\n"
+        );
+    }
+}
@@ -0,0 +1,427 @@
+/// Declare a store type whose tokens are distinct from those of any other store
+///
+/// `declare_store!(InputStore, InputToken)` generates a newtype `InputStore`
+/// around a `Store`, whose tokens are of type `InputToken<V>`. Tokens of a
+/// store declared this way can only be used with this store, mixing them
+/// with another store is a compile error. This is purely type-level: the
+/// generated types are thin wrappers with no runtime cost.
+///
+/// ```
+/// # #[macro_use] extern crate token_store;
+/// declare_store!(pub InputStore, InputToken);
+/// declare_store!(pub RenderStore, RenderToken);
+///
+/// # fn main() {
+/// let mut input = InputStore::new();
+/// let token: InputToken<u32> = input.insert(42);
+/// assert_eq!(*input.get(&token), 42);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! declare_store {
+    ($vis:vis $store:ident, $token:ident) => {
+        /// A store with its own token type
+        $vis struct $store($crate::Store);
+
+        /// A token of its dedicated store type
+        $vis struct $token<V>($crate::Token<V>);
+
+        impl<V> Clone for $token<V> {
+            fn clone(&self) -> $token<V> {
+                $token(self.0.clone())
+            }
+        }
+
+        impl<V> $token<V> {
+            /// Check wether this token is still valid
+            #[allow(dead_code)]
+            pub fn is_valid(&self) -> bool {
+                self.0.is_valid()
+            }
+        }
+
+        impl Default for $store {
+            fn default() -> $store {
+                $store::new()
+            }
+        }
+
+        #[allow(dead_code)]
+        impl $store {
+            /// Create a new store
+            pub fn new() -> $store {
+                $store($crate::Store::new())
+            }
+
+            /// Insert a new value in this store
+            pub fn insert<V: ::std::any::Any + 'static>(&mut self, value: V) -> $token<V> {
+                $token(self.0.insert(value))
+            }
+
+            /// Check wether this store contains the value represented by this token
+            pub fn contains<V: ::std::any::Any + 'static>(&self, token: &$token<V>) -> bool {
+                self.0.contains(&token.0)
+            }
+
+            /// Access value previously inserted in this store
+            pub fn get<V: ::std::any::Any + 'static>(&self, token: &$token<V>) -> &V {
+                self.0.get(&token.0)
+            }
+
+            /// Mutably access value previously inserted in this store
+            pub fn get_mut<V: ::std::any::Any + 'static>(&mut self, token: &$token<V>) -> &mut V {
+                self.0.get_mut(&token.0)
+            }
+
+            /// Replace a value previously inserted in this store
+            pub fn replace<V: ::std::any::Any + 'static>(&mut self, token: &$token<V>, value: V) -> V {
+                self.0.replace(&token.0, value)
+            }
+
+            /// Remove a value previously inserted in this store
+            pub fn remove<V: ::std::any::Any + 'static>(&mut self, token: $token<V>) -> V {
+                self.0.remove(token.0)
+            }
+        }
+    };
+}
+
+/// Declare a struct whose fields are inserted in a store as separate values
+///
+/// ```
+/// # #[macro_use] extern crate token_store;
+/// # use token_store::Store;
+/// store_bundle! {
+///     pub struct Player {
+///         pub name: String,
+///         pub health: u32,
+///     }
+///     pub struct PlayerTokens;
+/// }
+///
+/// # fn main() {
+/// let mut store = Store::new();
+/// let player = Player { name: "Alice".into(), health: 100 };
+/// let tokens = Player::insert_bundle(&mut store, player);
+/// *store.get_mut(&tokens.health) -= 10;
+/// let player = Player::remove_bundle(&mut store, tokens);
+/// assert_eq!(player.health, 90);
+/// # }
+/// ```
+///
+/// This declares the struct `Player` as written, as well as a struct
+/// `PlayerTokens` with a public `Token<T>` for each field of `Player`.
+/// `Player::insert_bundle` inserts every field of a `Player` in the store
+/// and returns their tokens, `Player::remove_bundle` removes them all and
+/// reassembles the `Player`.
+#[macro_export]
+macro_rules! store_bundle {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            $($fvis:vis $field:ident : $ty:ty),* $(,)*
+        }
+        $tvis:vis struct $tokens:ident;
+    ) => {
+        $(#[$attr])*
+        $vis struct $name {
+            $($fvis $field: $ty),*
+        }
+
+        /// The tokens of the fields of a bundle inserted in a store
+        $tvis struct $tokens {
+            $(
+                #[allow(missing_docs)]
+                pub $field: $crate::Token<$ty>
+            ),*
+        }
+
+        impl Clone for $tokens {
+            fn clone(&self) -> $tokens {
+                $tokens {
+                    $($field: self.$field.clone()),*
+                }
+            }
+        }
+
+        #[allow(dead_code)]
+        impl $name {
+            /// Insert every field of this bundle in the store
+            pub fn insert_bundle(store: &mut $crate::Store, value: $name) -> $tokens {
+                $tokens {
+                    $($field: store.insert(value.$field)),*
+                }
+            }
+
+            /// Remove every field of this bundle from the store
+            ///
+            /// Panics if any of the values was already removed.
+            pub fn remove_bundle(store: &mut $crate::Store, tokens: $tokens) -> $name {
+                $name {
+                    $($field: store.remove(tokens.$field)),*
+                }
+            }
+        }
+    };
+}
+
+/// Declare a newtype token for values of a given type
+///
+/// `domain_token!(pub SurfaceToken: Surface)` generates a newtype
+/// `SurfaceToken` around a `Token<Surface>`, so that an API can require a
+/// `SurfaceToken` rather than any `Token<Surface>`. It dereferences to the
+/// wrapped token, so it can be used directly with the methods of the store,
+/// and converts from and into it.
+///
+/// ```
+/// # #[macro_use] extern crate token_store;
+/// # use token_store::Store;
+/// pub struct Surface {
+///     pub width: u32,
+/// }
+///
+/// domain_token!(pub SurfaceToken: Surface);
+///
+/// fn resize(store: &mut Store, surface: &SurfaceToken, width: u32) {
+///     store.get_mut(surface).width = width;
+/// }
+///
+/// # fn main() {
+/// let mut store = Store::new();
+/// let surface = SurfaceToken::from(store.insert(Surface { width: 640 }));
+/// resize(&mut store, &surface, 800);
+/// assert_eq!(store.get(&surface).width, 800);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! domain_token {
+    ($(#[$attr:meta])* $vis:vis $name:ident : $ty:ty) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        $vis struct $name($crate::Token<$ty>);
+
+        impl ::std::ops::Deref for $name {
+            type Target = $crate::Token<$ty>;
+            fn deref(&self) -> &$crate::Token<$ty> {
+                &self.0
+            }
+        }
+
+        impl ::std::convert::From<$crate::Token<$ty>> for $name {
+            fn from(token: $crate::Token<$ty>) -> $name {
+                $name(token)
+            }
+        }
+
+        impl ::std::convert::From<$name> for $crate::Token<$ty> {
+            fn from(token: $name) -> $crate::Token<$ty> {
+                token.0
+            }
+        }
+
+        impl $crate::Key for $name {
+            fn any_token(&self) -> $crate::AnyToken {
+                $crate::AnyToken::from(&self.0)
+            }
+        }
+    };
+}
+
+/// Dispatch on the concrete type of a type-erased value
+///
+/// `match_any!(value, s: Surface => ..., o: Output => ..., _ => ...)` tries
+/// to downcast `value`, a `&dyn Any`, to each type in turn, and evaluates
+/// the expression of the first one that matches with the downcast reference
+/// bound to the given name. The last arm is evaluated if none matched.
+///
+/// ```
+/// # #[macro_use] extern crate token_store;
+/// # use token_store::Store;
+/// # fn main() {
+/// let mut store = Store::new();
+/// store.insert(42u32);
+/// store.insert(String::from("I like trains"));
+/// let descriptions = store
+///     .iter()
+///     .map(|(_, value)| match_any!(value,
+///         n: u32 => format!("number {}", n),
+///         s: String => format!("string {}", s),
+///         _ => String::from("unknown"),
+///     ))
+///     .collect::<Vec<_>>();
+/// assert_eq!(descriptions, ["number 42", "string I like trains"]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! match_any {
+    ($value:expr, $($bind:ident : $ty:ty => $arm:expr,)* _ => $default:expr $(,)*) => {{
+        let value: &dyn (::std::any::Any) = $value;
+        match_any!(@arms value, $($bind : $ty => $arm,)* _ => $default)
+    }};
+    (@arms $value:ident, $bind:ident : $ty:ty => $arm:expr, $($rest:tt)*) => {
+        if let Some($bind) = $value.downcast_ref::<$ty>() {
+            $arm
+        } else {
+            match_any!(@arms $value, $($rest)*)
+        }
+    };
+    (@arms $value:ident, _ => $default:expr) => {
+        $default
+    };
+}
+
+/// Declare a struct-like wrapper around a store, with named accessors
+///
+/// ```
+/// # #[macro_use] extern crate token_store;
+/// store_facade! {
+///     pub struct AppState {
+///         name, name_mut: String,
+///         counter, counter_mut: u32,
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut app = AppState::new("I like trains".into(), 0);
+/// *app.counter_mut() += 1;
+/// assert_eq!(*app.counter(), 1);
+/// let extra = app.store_mut().insert(42);
+/// assert_eq!(*app.store().get(&extra), 42);
+/// # }
+/// ```
+///
+/// This declares `AppState`, owning a `Store` in which a value is inserted
+/// for each field. `AppState::new` takes the initial values of all fields in
+/// order, and each field gets an accessor and a mutable accessor with the
+/// given names. The tokens of the fields are kept inside the wrapper, while
+/// `store` and `store_mut` give access to the store for other values.
+#[macro_export]
+macro_rules! store_facade {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            $($field:ident, $field_mut:ident : $ty:ty),* $(,)*
+        }
+    ) => {
+        $(#[$attr])*
+        $vis struct $name {
+            facade_store: $crate::Store,
+            $($field: $crate::Token<$ty>),*
+        }
+
+        #[allow(dead_code)]
+        impl $name {
+            /// Create the store and insert the initial value of every field
+            #[allow(clippy::too_many_arguments)]
+            pub fn new($($field: $ty),*) -> $name {
+                let mut facade_store = $crate::Store::new();
+                $(let $field = facade_store.insert($field);)*
+                $name {
+                    facade_store,
+                    $($field),*
+                }
+            }
+
+            /// Access the underlying store
+            pub fn store(&self) -> &$crate::Store {
+                &self.facade_store
+            }
+
+            /// Mutably access the underlying store
+            pub fn store_mut(&mut self) -> &mut $crate::Store {
+                &mut self.facade_store
+            }
+
+            $(
+                #[allow(missing_docs)]
+                pub fn $field(&self) -> &$ty {
+                    self.facade_store.get(&self.$field)
+                }
+
+                #[allow(missing_docs)]
+                pub fn $field_mut(&mut self) -> &mut $ty {
+                    self.facade_store.get_mut(&self.$field)
+                }
+            )*
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    declare_store!(InputStore, InputToken);
+
+    #[test]
+    fn declared_store() {
+        let mut store = InputStore::new();
+        let token = store.insert(42);
+        *store.get_mut(&token) += 1;
+        assert!(store.contains(&token));
+        assert_eq!(store.replace(&token, 12), 43);
+        assert_eq!(store.remove(token.clone()), 12);
+        assert!(!token.is_valid());
+    }
+
+    store_bundle! {
+        struct Surface {
+            title: String,
+            size: (u32, u32),
+        }
+        struct SurfaceTokens;
+    }
+
+    domain_token!(TitleToken: String);
+
+    #[test]
+    fn domain_token() {
+        let mut store = ::Store::new();
+        let title = TitleToken::from(store.insert(String::from("I like trains")));
+        store.get_mut(&title).push('!');
+        assert_eq!(store.get(&title), "I like trains!");
+        let token: ::Token<String> = title.clone().into();
+        assert_eq!(store.remove(token), "I like trains!");
+        assert!(!title.is_valid());
+    }
+
+    #[test]
+    fn match_any() {
+        let values: Vec<Box<dyn (::std::any::Any)>> = vec![Box::new(1u8), Box::new('a'), Box::new(2.0f32)];
+        let kinds = values
+            .iter()
+            .map(|value| match_any!(&**value, _n: u8 => "u8", _c: char => "char", _ => "other"))
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, ["u8", "char", "other"]);
+    }
+
+    store_facade! {
+        struct Seat {
+            name, name_mut: String,
+            focus, focus_mut: Option<u32>,
+        }
+    }
+
+    #[test]
+    fn facade() {
+        let mut seat = Seat::new("seat0".into(), None);
+        *seat.focus_mut() = Some(3);
+        seat.name_mut().push('!');
+        assert_eq!(seat.name(), "seat0!");
+        assert_eq!(*seat.focus(), Some(3));
+        assert_eq!(seat.store().len(), 2);
+    }
+
+    #[test]
+    fn bundle_roundtrip() {
+        let mut store = ::Store::new();
+        let surface = Surface {
+            title: "I like trains".into(),
+            size: (640, 480),
+        };
+        let tokens = Surface::insert_bundle(&mut store, surface);
+        assert_eq!(store.get(&tokens.title), "I like trains");
+        store.get_mut(&tokens.size).0 = 800;
+        let surface = Surface::remove_bundle(&mut store, tokens.clone());
+        assert_eq!(surface.size, (800, 480));
+        assert!(!tokens.title.is_valid());
+    }
+}
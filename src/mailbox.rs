@@ -0,0 +1,89 @@
+use super::{Store, StoreProxy, Token};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+// The messages posted to each slot, by type
+pub(crate) type Mailboxes = HashMap<(usize, TypeId), Vec<Box<dyn Any>>>;
+
+impl Store {
+    /// Post a message to a value of this store
+    ///
+    /// This only needs a shared reference to the store, and is allowed while
+    /// the value is borrowed, for example from within `with_value`. The
+    /// messages of each type sent to a value are queued until they are
+    /// drained with `drain_mailbox`, and dropped if the value is removed.
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn post<V: Any + 'static, M: Any + 'static>(&self, token: &Token<V>, message: M) {
+        if !self.contains(token) {
+            panic!("Attempted to post a message to a value that was already removed!");
+        }
+        self.mailboxes
+            .borrow_mut()
+            .entry((token.id, TypeId::of::<M>()))
+            .or_default()
+            .push(Box::new(message));
+    }
+
+    /// Take the messages of type `M` posted to a value
+    ///
+    /// The messages are returned in the order they were posted.
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn drain_mailbox<M: Any + 'static, V: Any + 'static>(&mut self, token: &Token<V>) -> Vec<M> {
+        if !self.contains(token) {
+            self.invalid_access(token);
+        }
+        match self.mailboxes.get_mut().remove(&(token.id, TypeId::of::<M>())) {
+            Some(messages) => messages
+                .into_iter()
+                .map(|message| *message.downcast().unwrap())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // Drops the messages posted to a slot
+    pub(crate) fn clear_mailbox(&mut self, id: usize) {
+        let mailboxes = self.mailboxes.get_mut();
+        if !mailboxes.is_empty() {
+            mailboxes.retain(|&(mailbox_id, _), _| mailbox_id != id);
+        }
+    }
+}
+
+impl<'store> StoreProxy<'store> {
+    /// Post a message to a value of the proxified store
+    ///
+    /// Unlike other accesses, this is allowed for the values that are
+    /// currently borrowed. See `Store::post` for full documentation.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn post<V: Any + 'static, M: Any + 'static>(&self, token: &Token<V>, message: M) {
+        self.store.post(token, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mailboxes() {
+        let mut store = Store::new();
+        let token = store.insert(0);
+        store.with_value(&token, |proxy, _| {
+            proxy.post(&token, 1);
+            proxy.post(&token, 2);
+            proxy.post(&token, "I like trains");
+        });
+        assert_eq!(store.drain_mailbox::<i32, _>(&token), vec![1, 2]);
+        assert!(store.drain_mailbox::<i32, _>(&token).is_empty());
+        store.post(&token, 3);
+        store.remove(token.clone());
+        let token = store.insert(0);
+        assert!(store.drain_mailbox::<i32, _>(&token).is_empty());
+        assert!(store.drain_mailbox::<&str, _>(&token).is_empty());
+    }
+}
@@ -0,0 +1,71 @@
+use super::Store;
+use std::time::{Duration, Instant};
+
+// Number of slots swept between two checks of the clock
+const BATCH: usize = 64;
+
+impl Store {
+    /// Reclaim slots incrementally, for about `budget` at most
+    ///
+    /// This does the work of `prune` and `purge` a few slots at a time:
+    /// dead weak references and soft-removed values are removed, and their
+    /// slots become available again. As with `purge`, no event is sent for
+    /// the soft-removed values. Each call resumes where the previous one
+    /// stopped, and always makes some progress, even with a zero budget.
+    ///
+    /// Returns `true` if the sweep reached the end of the store, in which case
+    /// the next call starts over from the first slot.
+    pub fn maintain(&mut self, budget: Duration) -> bool {
+        let start = Instant::now();
+        loop {
+            for _ in 0..BATCH {
+                if self.maintenance_cursor >= self.values.len() {
+                    self.maintenance_cursor = 0;
+                    return true;
+                }
+                let id = self.maintenance_cursor;
+                self.maintenance_cursor += 1;
+                self.reclaim(id);
+            }
+            if start.elapsed() >= budget {
+                return false;
+            }
+        }
+    }
+
+    // Removes the value of a slot if it is a dead weak reference or was
+    // soft-removed
+    fn reclaim(&mut self, id: usize) {
+        if self.tombstones.contains_key(&id) {
+            self.purge_at(id);
+        } else if self.is_dead_weak(id) {
+            self.finalize_at(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn maintain() {
+        let mut store = Store::new();
+        let events = store.events();
+        let shared = (0..100).map(Rc::new).collect::<Vec<_>>();
+        let weak = shared.iter().map(|rc| store.insert_weak(rc)).collect::<Vec<_>>();
+        let hidden = store.insert(1);
+        store.soft_remove(&hidden);
+        drop(shared);
+        assert!(!store.maintain(Duration::from_secs(0)));
+        assert!(!weak[0].is_valid());
+        assert!(weak[99].is_valid());
+        assert!(store.maintain(Duration::from_secs(0)));
+        assert!(!weak[99].is_valid());
+        assert!(!hidden.is_valid());
+        assert!(store.is_empty());
+        // the insertions, the removals of the weak references, and the soft removal
+        assert_eq!(events.try_iter().count(), 202);
+    }
+}
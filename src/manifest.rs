@@ -0,0 +1,87 @@
+use super::{Slot, Store};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::mem;
+
+/// A description of the contents of a store, without the values themselves
+///
+/// It is created by `Store::manifest`, and is meant to be attached to bug
+/// or crash reports.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    /// Total number of slots of the store
+    pub slots: usize,
+    /// Every type with values in the store, sorted by name
+    pub types: Vec<TypeEntry>,
+}
+
+/// The part of a `Manifest` describing one type of values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeEntry {
+    /// The name of the type
+    pub type_name: &'static str,
+    /// Number of values of this type in the store
+    pub count: usize,
+    /// Total size of these values, not counting what they point to
+    pub bytes: usize,
+}
+
+impl fmt::Display for Manifest {
+    // One line per type, tab separated, after a header line
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "slots\t{}", self.slots)?;
+        for entry in &self.types {
+            writeln!(f, "{}\t{}\t{}", entry.type_name, entry.count, entry.bytes)?;
+        }
+        Ok(())
+    }
+}
+
+impl Store {
+    /// Describe the contents of this store
+    ///
+    /// The manifest lists the types of the stored values with their counts
+    /// and sizes, it does not contain the values.
+    pub fn manifest(&self) -> Manifest {
+        let mut types = BTreeMap::new();
+        for id in 0..self.values.len() {
+            if let Slot {
+                type_name,
+                value: Some(ref boxed),
+                ..
+            } = self.values[id]
+            {
+                let entry = types.entry(type_name).or_insert((0, 0));
+                entry.0 += 1;
+                entry.1 += mem::size_of_val(&**boxed);
+            }
+        }
+        Manifest {
+            slots: self.values.len(),
+            types: types
+                .into_iter()
+                .map(|(type_name, (count, bytes))| TypeEntry { type_name, count, bytes })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest() {
+        let mut store = Store::new();
+        store.insert(1u32);
+        store.insert(2u32);
+        let token = store.insert(3u64);
+        store.remove(token);
+        store.insert(String::new());
+        let manifest = store.manifest();
+        assert_eq!(manifest.slots, 3);
+        assert_eq!(manifest.types.len(), 2);
+        assert_eq!(manifest.types[1], TypeEntry { type_name: "u32", count: 2, bytes: 8 });
+        assert_eq!(manifest.to_string().lines().count(), 3);
+    }
+}
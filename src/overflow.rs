@@ -0,0 +1,111 @@
+use super::Store;
+
+/// What happens when the generation counter of a slot overflows
+///
+/// See `Store::set_generation_overflow`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenerationOverflow {
+    /// The counter wraps back to zero, which is the default
+    ///
+    /// A token kept around for 2^32 reuses of its slot can then resolve to
+    /// an unrelated value of the same type.
+    Wrap,
+    /// The slot is never reused again
+    ///
+    /// This rules out any aliasing, at the cost of one slot of memory for
+    /// each exhausted slot.
+    Retire,
+    /// Removing the value panics
+    Panic,
+}
+
+impl Store {
+    /// Set what happens when the generation counter of a slot overflows
+    ///
+    /// The counter of a slot is incremented each time its value is removed
+    /// or its tokens are revoked. Defaults to `GenerationOverflow::Wrap`.
+    ///
+    /// With `GenerationOverflow::Retire`, revoking the tokens of, or
+    /// migrating, a value whose slot is exhausted panics, as the value can
+    /// not change slot.
+    pub fn set_generation_overflow(&mut self, policy: GenerationOverflow) {
+        self.generation_overflow = policy;
+    }
+
+    /// Number of slots whose generation counter will overflow within
+    /// `margin` more removals
+    ///
+    /// Retired slots are not counted.
+    pub fn slots_near_exhaustion(&self, margin: u32) -> usize {
+        (0..self.values.len())
+            .filter(|id| !self.retired.contains(id))
+            .filter(|&id| u32::MAX - self.values[id].generation < margin)
+            .count()
+    }
+
+    /// Number of slots retired by `GenerationOverflow::Retire`
+    pub fn retired_slots(&self) -> usize {
+        self.retired.len()
+    }
+
+    // Increments the generation of a slot, returns false if the slot must
+    // be retired instead
+    pub(crate) fn bump_generation(&mut self, id: usize) -> bool {
+        let slot = &mut self.values[id];
+        if slot.generation < u32::MAX {
+            slot.generation += 1;
+            return true;
+        }
+        match self.generation_overflow {
+            GenerationOverflow::Wrap => {
+                slot.generation = 0;
+                true
+            }
+            GenerationOverflow::Retire => false,
+            GenerationOverflow::Panic => panic!("Attempted to overflow the generation counter of slot {}.", id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Creates a store whose single vacant slot is at the given generation
+    fn store_at(generation: u32, policy: GenerationOverflow) -> Store {
+        let mut store = Store::new();
+        store.set_generation_overflow(policy);
+        let token = store.insert(0);
+        store.remove(token);
+        store.values[0].generation = generation;
+        store
+    }
+
+    #[test]
+    fn retire() {
+        let mut store = store_at(u32::MAX, GenerationOverflow::Retire);
+        let token = store.insert(1);
+        assert_eq!(store.slots_near_exhaustion(2), 1);
+        store.remove(token.clone());
+        assert_eq!(store.retired_slots(), 1);
+        assert_eq!(store.slots_near_exhaustion(2), 0);
+        assert_ne!(store.insert(2).id, token.id);
+        assert!(store.debug_validate().is_ok());
+    }
+
+    #[test]
+    fn wrap() {
+        let mut store = store_at(u32::MAX, GenerationOverflow::Wrap);
+        let token = store.insert(1);
+        store.remove(token);
+        assert_eq!(store.insert(2).generation, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempted to overflow the generation counter of slot 0.")]
+    fn panic() {
+        let mut store = store_at(u32::MAX, GenerationOverflow::Panic);
+        let token = store.insert(1);
+        store.remove(token);
+    }
+}
@@ -0,0 +1,64 @@
+use super::{caller_provenance, Store};
+use std::any::TypeId;
+use std::collections::HashMap;
+
+impl Store {
+    /// Split this store into one store per type of value
+    ///
+    /// Each value is moved to the store of its type, in the same slot index
+    /// and generation, so the raw parts and handles of the original tokens
+    /// can be resolved in the new stores. The original tokens themselves are
+    /// invalidated. Finalizers and stable identifiers move with their value,
+    /// and the clone and comparison registrations are copied to every new
+    /// store, but links and pending messages are dropped, and so are the
+    /// values hidden by `soft_remove`.
+    pub fn partition_by_type(mut self) -> HashMap<TypeId, Store> {
+        let provenance = caller_provenance();
+        let mut stores = HashMap::new();
+        for id in 0..self.values.len() {
+            if self.values[id].value.is_none() {
+                continue;
+            }
+            let type_id = self.values[id].type_id;
+            let generation = self.values[id].generation;
+            let finalizer = self.values[id].finalizer.take();
+            let stable_id = self.clear_stable_id(id);
+            let value = self.remove_at(id);
+            let store = stores.entry(type_id).or_insert_with(|| {
+                let mut store = Store::new();
+                store.cloners = self.cloners.clone();
+                store.comparators = self.comparators.clone();
+                store
+            });
+            let (type_name, origin) = (self.values[id].type_name, self.values[id].provenance);
+            store.place_at(id, generation, type_name, origin, value, provenance);
+            store.values[id].finalizer = finalizer;
+            if let Some(stable_id) = stable_id {
+                store.assign_stable_id(id, stable_id);
+            }
+        }
+        stores
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partition_by_type() {
+        let mut store = Store::new();
+        let number = store.insert(42);
+        let text = store.insert(String::from("I like trains"));
+        store.insert(7);
+        let (index, generation) = text.clone().into_raw_parts();
+        let mut stores = store.partition_by_type();
+        assert!(!number.is_valid());
+        assert_eq!(stores.len(), 2);
+        assert_eq!(stores[&TypeId::of::<i32>()].len(), 2);
+        let strings = stores.remove(&TypeId::of::<String>()).unwrap();
+        let text = strings.token_from_raw_parts::<String>(index, generation).unwrap();
+        assert_eq!(strings.get(&text), "I like trains");
+        assert!(strings.debug_validate().is_ok());
+    }
+}
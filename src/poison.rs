@@ -0,0 +1,116 @@
+use super::{Store, Token};
+use std::any::Any;
+use std::error::Error;
+use std::fmt;
+
+/// The reason why a value could not be accessed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessError {
+    /// The value was removed from the store
+    Removed,
+    /// A closure given access to the value panicked
+    ///
+    /// The value may have been left in an inconsistent state. It can be
+    /// accessed again after a call to `Store::unpoison`.
+    Poisoned,
+}
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AccessError::Removed => f.write_str("the value was already removed"),
+            AccessError::Poisoned => f.write_str("the value is poisoned"),
+        }
+    }
+}
+
+impl Error for AccessError {}
+
+impl Store {
+    #[cold]
+    #[inline(never)]
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub(crate) fn failed_access<V: Any + 'static>(&self, token: &Token<V>, error: AccessError) -> ! {
+        self.report_failure(token, error);
+        match error {
+            AccessError::Removed => self.invalid_access(token),
+            AccessError::Poisoned => panic!(
+                "Attempted to access a state value that was poisoned by a panic! (slot {}, type {})",
+                token.id,
+                self.values[token.id].type_name
+            ),
+        }
+    }
+
+    /// Try to access a value previously inserted in this store
+    ///
+    /// Unlike `get`, this returns an error instead of panicking if the value
+    /// was removed or is poisoned.
+    pub fn try_get<V: Any + 'static>(&self, token: &Token<V>) -> Result<&V, AccessError> {
+        if self.is_poisoned(token) {
+            return Err(AccessError::Poisoned);
+        }
+        let value = self.lookup::<V>(token.id, token.generation).ok_or(AccessError::Removed)?;
+        self.touch(token.id);
+        Ok(value)
+    }
+
+    /// Try to mutably access a value previously inserted in this store
+    ///
+    /// Unlike `get_mut`, this returns an error instead of panicking if the
+    /// value was removed or is poisoned.
+    pub fn try_get_mut<V: Any + 'static>(&mut self, token: &Token<V>) -> Result<&mut V, AccessError> {
+        if self.is_poisoned(token) {
+            return Err(AccessError::Poisoned);
+        }
+        self.touch(token.id);
+        self.lookup_mut::<V>(token.id, token.generation).ok_or(AccessError::Removed)
+    }
+
+    /// Check wether a value is poisoned
+    ///
+    /// A value is poisoned when the closure given to `with_value` for it
+    /// panics. Accessing a poisoned value panics, until it is unpoisoned.
+    pub fn is_poisoned<V: Any + 'static>(&self, token: &Token<V>) -> bool {
+        match self.values.get(token.id) {
+            Some(slot) => slot.poisoned && slot.generation == token.generation,
+            None => false,
+        }
+    }
+
+    /// Clear the poisoned state of a value
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    pub fn unpoison<V: Any + 'static>(&mut self, token: &Token<V>) {
+        if !self.contains(token) {
+            self.invalid_access(token);
+        }
+        self.values[token.id].poisoned = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    #[test]
+    fn poisoning() {
+        let mut store = Store::new();
+        let token = store.insert(1);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            store.with_value(&token, |_, value| {
+                *value += 1;
+                panic!("oops");
+            })
+        }));
+        assert!(result.is_err());
+        assert!(store.is_poisoned(&token));
+        assert_eq!(store.try_get(&token), Err(AccessError::Poisoned));
+        assert!(catch_unwind(AssertUnwindSafe(|| *store.get(&token))).is_err());
+        store.unpoison(&token);
+        assert_eq!(store.try_get(&token), Ok(&2));
+        store.remove(token.clone());
+        assert_eq!(store.try_get_mut(&token), Err(AccessError::Removed));
+    }
+}
@@ -0,0 +1,97 @@
+use super::{AccessError, Store, Token};
+use std::any::{type_name, Any};
+
+// Called on every failed access, before it is handled
+pub(crate) type AccessHook = Box<dyn Fn(&AccessFailure)>;
+
+/// A description of a failed access to a value
+///
+/// It is given to the hook set with `Store::set_access_hook`.
+#[derive(Debug, Clone)]
+pub struct AccessFailure {
+    /// Why the access failed
+    pub error: AccessError,
+    /// The slot index of the token
+    pub index: u32,
+    /// The generation of the token
+    pub generation: u32,
+    /// The name of the type of the value of the token
+    pub type_name: &'static str,
+}
+
+impl Store {
+    /// Set a hook to be called when an access to a value fails
+    ///
+    /// The hook is called when `get` or `get_mut` are about to panic, and
+    /// when `get_or` or `get_mut_or` fall back to their default value. It
+    /// can be used to log stale accesses, for example. The `try_*` access
+    /// methods do not call it, as they leave the handling of the failure to
+    /// their caller.
+    pub fn set_access_hook<F: Fn(&AccessFailure) + 'static>(&mut self, hook: F) {
+        self.access_hook = Some(Box::new(hook));
+    }
+
+    /// Access a value, or a fallback if the access fails
+    ///
+    /// Unlike `get`, this never panics: if the value was removed or is
+    /// poisoned, the access hook is called and `fallback` is returned.
+    pub fn get_or<'a, V: Any + 'static>(&'a self, token: &Token<V>, fallback: &'a V) -> &'a V {
+        match self.try_get(token) {
+            Ok(value) => value,
+            Err(error) => {
+                self.report_failure(token, error);
+                fallback
+            }
+        }
+    }
+
+    /// Mutably access a value, or a fallback if the access fails
+    ///
+    /// This is the mutable counterpart of `get_or`.
+    pub fn get_mut_or<'a, V: Any + 'static>(&'a mut self, token: &Token<V>, fallback: &'a mut V) -> &'a mut V {
+        if let Err(error) = self.try_get(token) {
+            self.report_failure(token, error);
+            return fallback;
+        }
+        self.lookup_mut::<V>(token.id, token.generation).unwrap()
+    }
+
+    // Calls the access hook, if any
+    pub(crate) fn report_failure<V: Any + 'static>(&self, token: &Token<V>, error: AccessError) {
+        if let Some(ref hook) = self.access_hook {
+            hook(&AccessFailure {
+                error,
+                index: token.id as u32,
+                generation: token.generation,
+                type_name: type_name::<V>(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn access_hook() {
+        let failures = Rc::new(RefCell::new(Vec::new()));
+        let mut store = Store::new();
+        {
+            let failures = failures.clone();
+            store.set_access_hook(move |failure| failures.borrow_mut().push(failure.clone()));
+        }
+        let token = store.insert(42);
+        assert_eq!(*store.get_or(&token, &0), 42);
+        store.remove(token.clone());
+        assert_eq!(*store.get_or(&token, &0), 0);
+        *store.get_mut_or(&token, &mut 0) += 1;
+        assert_eq!(failures.borrow().len(), 2);
+        assert_eq!(failures.borrow()[0].error, AccessError::Removed);
+        assert_eq!(failures.borrow()[0].type_name, "i32");
+        assert!(store.try_get(&token).is_err());
+        assert_eq!(failures.borrow().len(), 2);
+    }
+}
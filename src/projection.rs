@@ -0,0 +1,87 @@
+use super::{Store, Token};
+use std::any::Any;
+
+/// A token giving access to a part of a value
+///
+/// It is created using `Token::project`, and gives access to the part of the
+/// value selected by its projection functions, through `Store::get_projected`
+/// and `Store::get_projected_mut`. It does not give access to the rest of the
+/// value.
+pub struct ProjToken<V, S> {
+    token: Token<V>,
+    get: fn(&V) -> &S,
+    get_mut: fn(&mut V) -> &mut S,
+}
+
+impl<V> Token<V> {
+    /// Derive a token giving access to a part of this value
+    ///
+    /// `get` and `get_mut` select this part, usually a field of the value.
+    pub fn project<S>(&self, get: fn(&V) -> &S, get_mut: fn(&mut V) -> &mut S) -> ProjToken<V, S> {
+        ProjToken {
+            token: self.clone(),
+            get,
+            get_mut,
+        }
+    }
+}
+
+impl<V, S> ProjToken<V, S> {
+    /// Check wether the value of this token is still valid
+    pub fn is_valid(&self) -> bool {
+        self.token.is_valid()
+    }
+}
+
+impl<V, S> Clone for ProjToken<V, S> {
+    fn clone(&self) -> ProjToken<V, S> {
+        ProjToken {
+            token: self.token.clone(),
+            get: self.get,
+            get_mut: self.get_mut,
+        }
+    }
+}
+
+impl Store {
+    /// Access the part of a value selected by a projected token
+    ///
+    /// Panics if the value was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn get_projected<V: Any + 'static, S>(&self, token: &ProjToken<V, S>) -> &S {
+        (token.get)(self.get(&token.token))
+    }
+
+    /// Mutably access the part of a value selected by a projected token
+    ///
+    /// Panics if the value was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn get_projected_mut<V: Any + 'static, S>(&mut self, token: &ProjToken<V, S>) -> &mut S {
+        (token.get_mut)(self.get_mut(&token.token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Window {
+        title: String,
+        size: (u32, u32),
+    }
+
+    #[test]
+    fn projection() {
+        let mut store = Store::new();
+        let token = store.insert(Window {
+            title: String::from("I like trains"),
+            size: (800, 600),
+        });
+        let size = token.project(|w| &w.size, |w| &mut w.size);
+        store.get_projected_mut(&size).0 = 1024;
+        assert_eq!(*store.get_projected(&size), (1024, 600));
+        assert_eq!(store.get(&token).title, "I like trains");
+        store.remove(token);
+        assert!(!size.is_valid());
+    }
+}
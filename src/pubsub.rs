@@ -0,0 +1,81 @@
+use super::{Store, StoreProxy, Token};
+use std::any::{Any, TypeId};
+
+type Handler<E> = Box<dyn FnMut(&mut StoreProxy, &E)>;
+
+/// A subscriber to the events of type `E` published in a store
+///
+/// It is created by `Store::subscribe`, and lives in the store like any
+/// other value: removing it from the store unsubscribes it.
+pub struct Subscriber<E> {
+    handler: Handler<E>,
+}
+
+impl Store {
+    /// Subscribe to the events of type `E` published in this store
+    ///
+    /// The handler is called for every event published with `publish`, with
+    /// a `StoreProxy` to access the other values of the store.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn subscribe<E, F>(&mut self, handler: F) -> Token<Subscriber<E>>
+    where
+        E: Any + 'static,
+        F: FnMut(&mut StoreProxy, &E) + 'static,
+    {
+        self.insert(Subscriber {
+            handler: Box::new(handler),
+        })
+    }
+
+    /// Publish an event to all its subscribers in this store
+    ///
+    /// The subscribers are called synchronously, in the order they subscribed.
+    /// Subscribers added while the event is delivered do not receive it.
+    /// Returns the number of subscribers that received the event.
+    pub fn publish<E: Any + 'static>(&mut self, event: E) -> usize {
+        let mut subscribers = (0..self.values.len())
+            .filter(|&id| self.values[id].type_id == TypeId::of::<Subscriber<E>>())
+            .filter_map(|id| {
+                let slot = &self.values[id];
+                self.token_from_raw_parts::<Subscriber<E>>(id as u32, slot.generation)
+                    .map(|token| (slot.serial, token))
+            })
+            .collect::<Vec<_>>();
+        subscribers.sort_by_key(|&(serial, _)| serial);
+        let mut delivered = 0;
+        for (_, token) in subscribers {
+            // the subscriber may have been removed by a previous one
+            if self.contains(&token) {
+                self.with_value(&token, |proxy, subscriber| (subscriber.handler)(proxy, &event));
+                delivered += 1;
+            }
+        }
+        delivered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn publish_subscribe() {
+        let mut store = Store::new();
+        let total = store.insert(0);
+        let first = {
+            let total = total.clone();
+            store.subscribe(move |proxy, event: &i32| *proxy.get_mut(&total) += *event)
+        };
+        {
+            let total = total.clone();
+            store.subscribe(move |proxy, event: &i32| *proxy.get_mut(&total) *= *event);
+        }
+        store.subscribe(|_, _: &String| panic!("Received an event of the wrong type!"));
+        assert_eq!(store.publish(3), 2);
+        assert_eq!(*store.get(&total), 9);
+        store.remove(first);
+        assert_eq!(store.publish(2), 1);
+        assert_eq!(*store.get(&total), 18);
+        assert_eq!(store.publish(0u8), 0);
+    }
+}
@@ -0,0 +1,89 @@
+use super::{AnyToken, Store, Token};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+// The parked values of each type with recycling enabled, with the pool size
+pub(crate) type Pools = HashMap<TypeId, (usize, Vec<Box<dyn Any>>)>;
+
+impl Store {
+    /// Keep up to `max` recycled values of type `V` for reuse
+    ///
+    /// Values removed with `recycle` are then parked instead of dropped, and
+    /// reused by `insert_recycled`. A size of 0 disables recycling and drops
+    /// the parked values.
+    pub fn enable_recycling<V: Any + 'static>(&mut self, max: usize) {
+        if max == 0 {
+            self.pools.remove(&TypeId::of::<V>());
+            return;
+        }
+        let pool = self.pools.entry(TypeId::of::<V>()).or_insert_with(|| (max, Vec::new()));
+        pool.0 = max;
+        pool.1.truncate(max);
+    }
+
+    /// Remove a value, parking it for reuse if recycling is enabled for `V`
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn recycle<V: Any + 'static>(&mut self, token: Token<V>) {
+        if self.lookup::<V>(token.id, token.generation).is_none() {
+            self.invalid_access(&token);
+        }
+        let id = token.id;
+        drop(token);
+        let boxed = self.remove_at(id);
+        if let Some(&mut (max, ref mut parked)) = self.pools.get_mut(&TypeId::of::<V>()) {
+            if parked.len() < max {
+                parked.push(boxed);
+            }
+        }
+    }
+
+    /// Insert a value, reusing a recycled one if possible
+    ///
+    /// `init` is given a parked value of type `V` if there is one, or a
+    /// default one, and should set it to the value to insert. Reusing the
+    /// parked value saves its allocation, as well as the buffers it owns.
+    ///
+    /// Panics if the initialized value is rejected by the validator of `V`.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn insert_recycled<V, F>(&mut self, init: F) -> Token<V>
+    where
+        V: Any + Default + 'static,
+        F: FnOnce(&mut V),
+    {
+        let mut boxed = self.pools
+            .get_mut(&TypeId::of::<V>())
+            .and_then(|&mut (_, ref mut parked)| parked.pop())
+            .unwrap_or_else(|| Box::new(V::default()));
+        init(boxed.downcast_mut::<V>().unwrap());
+        self.assert_valid(boxed.downcast_ref::<V>().unwrap());
+        self.make_room(&[]);
+        let token = self.allocate::<V>();
+        self.occupy_any(AnyToken::from(&token), boxed);
+        token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recycling() {
+        let mut store = Store::new();
+        store.enable_recycling::<Vec<u8>>(1);
+        let token = store.insert(Vec::with_capacity(64));
+        let address = store.get(&token) as *const Vec<u8>;
+        store.recycle(token);
+        let token = store.insert_recycled(|v: &mut Vec<u8>| {
+            v.clear();
+            v.push(42);
+        });
+        assert_eq!(store.get(&token), &[42]);
+        assert_eq!(store.get(&token) as *const Vec<u8>, address);
+        assert!(store.get(&token).capacity() >= 64);
+        let fresh = store.insert_recycled(|v: &mut Vec<u8>| v.push(1));
+        assert_eq!(store.get(&fresh), &[1]);
+    }
+}
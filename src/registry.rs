@@ -0,0 +1,97 @@
+use super::{Store, StoreHandle};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::{Rc, Weak};
+
+// The registered stores, by name
+type Registry = BTreeMap<String, Weak<RefCell<Store>>>;
+
+thread_local! {
+    static REGISTRY: RefCell<Registry> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// A per-thread registry of named stores, for debugging tools
+///
+/// Stores shared through a `StoreHandle` can be registered under a name, so
+/// that debug consoles or crash handlers can find and inspect all of them.
+/// The registry does not keep the stores alive: a store is forgotten once
+/// all its handles are dropped.
+///
+/// As stores can not be sent to other threads, this is not a process-wide
+/// registry: each thread has its own, and only sees the stores registered
+/// from that thread.
+pub struct ThreadStoreRegistry {
+    _private: (),
+}
+
+impl ThreadStoreRegistry {
+    /// Register a store under a name
+    ///
+    /// This replaces any store previously registered under the same name.
+    pub fn register(name: &str, handle: &StoreHandle) {
+        let store = Rc::downgrade(&handle.inner);
+        REGISTRY.with(|registry| registry.borrow_mut().insert(name.to_string(), store));
+    }
+
+    /// Remove a store from the registry
+    pub fn unregister(name: &str) {
+        REGISTRY.with(|registry| registry.borrow_mut().remove(name));
+    }
+
+    /// Get a handle to the store registered under a name
+    pub fn get(name: &str) -> Option<StoreHandle> {
+        REGISTRY.with(|registry| {
+            let inner = registry.borrow().get(name)?.upgrade()?;
+            Some(StoreHandle { inner })
+        })
+    }
+
+    /// The names of the registered stores which are still alive, sorted
+    pub fn names() -> Vec<String> {
+        REGISTRY.with(|registry| {
+            let mut registry = registry.borrow_mut();
+            registry.retain(|_, store| store.upgrade().is_some());
+            registry.keys().cloned().collect()
+        })
+    }
+
+    /// Call a function on every registered store which is still alive
+    ///
+    /// The stores are visited in the order of their names. Stores which are
+    /// currently mutably borrowed are skipped.
+    pub fn for_each<F: FnMut(&str, &Store)>(mut f: F) {
+        for name in ThreadStoreRegistry::names() {
+            if let Some(handle) = ThreadStoreRegistry::get(&name) {
+                if let Ok(store) = handle.inner.try_borrow() {
+                    f(&name, &store);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry() {
+        let compositor = StoreHandle::new();
+        compositor.insert(42);
+        ThreadStoreRegistry::register("compositor", &compositor);
+        {
+            let input = StoreHandle::new();
+            ThreadStoreRegistry::register("input", &input);
+            assert_eq!(ThreadStoreRegistry::names(), vec!["compositor", "input"]);
+        }
+        assert_eq!(ThreadStoreRegistry::names(), vec!["compositor"]);
+        let mut lens = Vec::new();
+        ThreadStoreRegistry::for_each(|name, store| lens.push((name.to_string(), store.len())));
+        assert_eq!(lens, vec![("compositor".to_string(), 1)]);
+        ::std::thread::spawn(|| assert!(ThreadStoreRegistry::names().is_empty()))
+            .join()
+            .unwrap();
+        ThreadStoreRegistry::unregister("compositor");
+        assert!(ThreadStoreRegistry::get("compositor").is_none());
+    }
+}
@@ -0,0 +1,77 @@
+use super::{Store, Token};
+use std::any::Any;
+use std::collections::HashMap;
+use std::mem;
+
+// The address of a boxed value, if it is not zero-sized
+//
+// Zero-sized values do not occupy memory, so their address does not
+// identify them.
+pub(crate) fn value_address(value: &dyn Any) -> Option<usize> {
+    if mem::size_of_val(value) == 0 {
+        None
+    } else {
+        Some(value as *const dyn Any as *const () as usize)
+    }
+}
+
+impl Store {
+    /// Enable constant-time reverse lookups with `token_of`
+    ///
+    /// This makes the store keep track of where each value lives in memory,
+    /// at the cost of a small overhead on every insertion and removal.
+    pub fn enable_reverse_lookup(&mut self) {
+        if self.addresses.is_some() {
+            return;
+        }
+        let mut addresses = HashMap::with_capacity(self.len);
+        for id in 0..self.values.len() {
+            if let Some(ref boxed) = self.values[id].value {
+                if let Some(address) = value_address(&**boxed) {
+                    addresses.insert(address, id);
+                }
+            }
+        }
+        self.addresses = Some(addresses);
+    }
+
+    /// Recover the token of a value from a reference to it
+    ///
+    /// `value` must be a reference to a value of this store, as obtained
+    /// with `get` for example. Returns `None` if it is not, or if `V` is
+    /// zero-sized, as zero-sized values cannot be told apart by address.
+    ///
+    /// This walks the store, unless `enable_reverse_lookup` was called.
+    pub fn token_of<V: Any + 'static>(&self, value: &V) -> Option<Token<V>> {
+        let address = value_address(value)?;
+        let id = match self.addresses {
+            Some(ref addresses) => *addresses.get(&address)?,
+            None => (0..self.values.len()).find(|&id| match self.values[id].value {
+                Some(ref boxed) => value_address(&**boxed) == Some(address),
+                None => false,
+            })?,
+        };
+        self.token_from_raw_parts(id as u32, self.values[id].generation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reverse_lookup() {
+        let mut store = Store::new();
+        let token = store.insert(42);
+        store.insert(0);
+        assert_eq!(store.token_of(store.get(&token)), Some(token.clone()));
+        store.enable_reverse_lookup();
+        assert_eq!(store.token_of(store.get(&token)), Some(token.clone()));
+        let other = store.insert(String::from("I like trains"));
+        assert_eq!(store.token_of(store.get(&other)), Some(other.clone()));
+        assert_eq!(store.token_of(&42), None);
+        store.remove(other);
+        let unit = store.insert(());
+        assert_eq!(store.token_of(store.get(&unit)), None);
+    }
+}
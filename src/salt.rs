@@ -0,0 +1,72 @@
+use super::Store;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_SALT: AtomicU64 = AtomicU64::new(0);
+
+// The salt of a new store, see `set_generation_salting`
+pub(crate) fn next_salt() -> Option<u64> {
+    if cfg!(debug_assertions) {
+        Some(NEXT_SALT.fetch_add(1, Ordering::Relaxed))
+    } else {
+        None
+    }
+}
+
+// Scrambles the bits of an integer (splitmix64)
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+impl Store {
+    /// Set wether the generations of new slots start at a salted value
+    ///
+    /// This is enabled by default in debug builds. Each store then has its
+    /// own salt, and the first generation of each slot is derived from it
+    /// and from the slot index, instead of being 0. Raw parts and handles
+    /// that were forged, corrupted, or created by another store are thus
+    /// rejected by `token_from_raw_parts` and `token_from_handle` instead of
+    /// resolving to an arbitrary value.
+    ///
+    /// Salted generations are below 2^31, so each slot can still be reused
+    /// at least 2^31 times before its generation overflows.
+    pub fn set_generation_salting(&mut self, enabled: bool) {
+        self.salt = match (enabled, self.salt) {
+            (false, _) => None,
+            (true, Some(salt)) => Some(salt),
+            (true, None) => Some(NEXT_SALT.fetch_add(1, Ordering::Relaxed)),
+        };
+    }
+
+    // The generation of a new slot
+    pub(crate) fn initial_generation(&self, id: usize) -> u32 {
+        match self.salt {
+            Some(salt) => (mix(mix(salt) ^ id as u64) as u32) & 0x7fff_ffff,
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn salted_generations() {
+        let mut first = Store::new();
+        let mut second = Store::new();
+        first.set_generation_salting(true);
+        second.set_generation_salting(true);
+        let token = first.insert(1);
+        second.insert(2);
+        let (index, generation) = token.clone().into_raw_parts();
+        assert!(first.token_from_raw_parts::<i32>(index, generation).is_some());
+        assert!(first.token_from_raw_parts::<i32>(index, 0).is_none());
+        assert!(second.token_from_raw_parts::<i32>(index, generation).is_none());
+        let mut unsalted = Store::new();
+        unsalted.set_generation_salting(false);
+        assert_eq!(unsalted.insert(3).into_raw_parts(), (0, 0));
+    }
+}
@@ -0,0 +1,106 @@
+use super::{AnyToken, Store, Token};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+// The values scheduled for removal by `remove_after`, by deadline
+pub(crate) type Schedule = BTreeMap<Instant, Vec<AnyToken>>;
+
+impl Store {
+    /// Schedule a value for removal once `delay` has elapsed
+    ///
+    /// The value is removed by the first call to `tick` past its deadline,
+    /// running its finalizer. Scheduling a value again replaces its previous
+    /// deadline. If the value is removed in the meantime, nothing happens. A
+    /// delay too large to be represented as an `Instant` means "never": the
+    /// value is then not scheduled, and any previous deadline is cancelled.
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn remove_after<V: Any + 'static>(&mut self, token: &Token<V>, delay: Duration) {
+        if !self.contains(token) {
+            self.invalid_access(token);
+        }
+        let token = AnyToken::from(token);
+        self.cancel_scheduled(&token);
+        if let Some(deadline) = Instant::now().checked_add(delay) {
+            self.schedule.entry(deadline).or_default().push(token);
+        }
+    }
+
+    /// Cancel the scheduled removal of a value
+    ///
+    /// Returns `false` if the value was not scheduled for removal.
+    pub fn cancel_removal<V: Any + 'static>(&mut self, token: &Token<V>) -> bool {
+        self.cancel_scheduled(&AnyToken::from(token))
+    }
+
+    /// Remove the values whose deadline is `now` or earlier
+    ///
+    /// Their finalizers are run, in the order of their deadlines. Returns the
+    /// number of removed values.
+    pub fn tick(&mut self, now: Instant) -> usize {
+        let later = match now.checked_add(Duration::from_nanos(1)) {
+            Some(later) => self.schedule.split_off(&later),
+            None => Schedule::new(),
+        };
+        let due = ::std::mem::replace(&mut self.schedule, later);
+        let mut removed = 0;
+        for token in due.into_values().flatten() {
+            if self.is_alive(&token) {
+                self.finalize_at(token.id);
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    // Removes a value from the schedule, returns wether it was there
+    fn cancel_scheduled(&mut self, token: &AnyToken) -> bool {
+        let mut found = false;
+        self.schedule.retain(|_, tokens| {
+            let len = tokens.len();
+            tokens.retain(|scheduled| scheduled != token);
+            found |= tokens.len() != len;
+            !tokens.is_empty()
+        });
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_after() {
+        let mut store = Store::new();
+        let soon = store.insert(1);
+        let later = store.insert(2);
+        let kept = store.insert(3);
+        store.remove_after(&soon, Duration::from_secs(0));
+        store.remove_after(&later, Duration::from_secs(0));
+        store.remove_after(&later, Duration::from_secs(3600));
+        store.remove_after(&kept, Duration::from_secs(0));
+        assert!(store.cancel_removal(&kept));
+        assert!(!store.cancel_removal(&kept));
+        let now = Instant::now();
+        assert_eq!(store.tick(now), 1);
+        assert!(!soon.is_valid());
+        assert!(later.is_valid());
+        assert_eq!(store.tick(now + Duration::from_secs(7200)), 1);
+        assert!(!later.is_valid());
+        assert!(kept.is_valid());
+    }
+
+    #[test]
+    fn remove_never() {
+        let mut store = Store::new();
+        let token = store.insert(1);
+        store.remove_after(&token, Duration::from_secs(0));
+        store.remove_after(&token, Duration::MAX);
+        assert!(!store.cancel_removal(&token));
+        assert_eq!(store.tick(Instant::now()), 0);
+        assert!(token.is_valid());
+    }
+}
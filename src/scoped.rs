@@ -0,0 +1,94 @@
+use super::{AnyToken, Store, Token};
+use std::any::Any;
+use std::ops::{Deref, DerefMut};
+
+/// A store in which some insertions are temporary
+///
+/// It is given by `Store::scoped`, and derefs to the underlying store. The
+/// values inserted with its own `insert` method are removed when it is
+/// dropped, which invalidates their tokens.
+pub struct Scoped<'store> {
+    store: &'store mut Store,
+    tokens: Vec<AnyToken>,
+}
+
+impl<'store> Scoped<'store> {
+    /// Insert a value that will be removed at the end of the scope
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn insert<V: Any + 'static>(&mut self, value: V) -> Token<V> {
+        let token = self.store.insert(value);
+        self.tokens.push(AnyToken::from(&token));
+        token
+    }
+}
+
+impl<'store> Deref for Scoped<'store> {
+    type Target = Store;
+    fn deref(&self) -> &Store {
+        self.store
+    }
+}
+
+impl<'store> DerefMut for Scoped<'store> {
+    fn deref_mut(&mut self) -> &mut Store {
+        self.store
+    }
+}
+
+impl<'store> Drop for Scoped<'store> {
+    fn drop(&mut self) {
+        // remove the most recent values first, like the store does when
+        // it is dropped
+        while let Some(token) = self.tokens.pop() {
+            if self.store.is_alive(&token) {
+                let id = token.id;
+                drop(token);
+                self.store.finalize_at(id);
+            }
+        }
+    }
+}
+
+impl Store {
+    /// Run a closure with temporary insertions
+    ///
+    /// The values inserted through the `Scoped` given to the closure are
+    /// removed from the store when it returns, or if it panics. Their
+    /// finalizers, if any, are run.
+    pub fn scoped<T, F: FnOnce(&mut Scoped) -> T>(&mut self, f: F) -> T {
+        let mut scoped = Scoped {
+            store: self,
+            tokens: Vec::new(),
+        };
+        f(&mut scoped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    #[test]
+    fn temporary_insertions() {
+        let mut store = Store::new();
+        let kept = store.insert(1);
+        let temporary = store.scoped(|scoped| {
+            let temporary = scoped.insert(2);
+            let other = scoped.insert(3);
+            scoped.remove(other);
+            assert_eq!(*scoped.get(&kept), 1);
+            temporary
+        });
+        assert!(!temporary.is_valid());
+        assert_eq!(store.len(), 1);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            store.scoped(|scoped| {
+                scoped.insert(4);
+                panic!("oops");
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(store.values_cloned::<i32>(), vec![1]);
+    }
+}
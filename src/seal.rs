@@ -0,0 +1,42 @@
+use super::Store;
+
+impl Store {
+    /// Forbid any further insertion in this store
+    ///
+    /// Once sealed, inserting a value panics, and `try_insert` and
+    /// `insert_with_id` fail. Values can still be accessed, replaced and
+    /// removed. A store can not be unsealed.
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    /// Check wether this store was sealed
+    pub fn is_sealed(&self) -> bool {
+        self.sealed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed() {
+        let mut store = Store::new();
+        let token = store.insert(1);
+        store.seal();
+        assert!(store.is_sealed());
+        assert_eq!(store.try_insert(2), Err(2));
+        assert_eq!(store.insert_with_id(5, 2).err(), Some(2));
+        *store.get_mut(&token) += 1;
+        assert_eq!(store.remove(token), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_sealed() {
+        let mut store = Store::new();
+        store.seal();
+        store.insert(1);
+    }
+}
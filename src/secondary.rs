@@ -0,0 +1,280 @@
+//! Secondary containers, attaching data to the tokens of a store
+
+use super::{AnyToken, Token};
+use std::collections::hash_map::{self, HashMap};
+
+/// A type designating a value of a store
+///
+/// This is implemented by both `Token<V>` and `AnyToken`, so that they can
+/// be used interchangeably as keys of secondary containers.
+pub trait Key {
+    /// Get the type-erased token designating this value
+    fn any_token(&self) -> AnyToken;
+}
+
+impl<V> Key for Token<V> {
+    fn any_token(&self) -> AnyToken {
+        AnyToken::from(self)
+    }
+}
+
+impl Key for AnyToken {
+    fn any_token(&self) -> AnyToken {
+        self.clone()
+    }
+}
+
+/// A sparse secondary map, attaching data to some tokens of a store
+///
+/// This map associates values of type `T` to tokens of a store, regardless
+/// of the type of the values these tokens represent. It is backed by a hash
+/// map, and is thus fit for attaching data to only a few values of a large
+/// store.
+///
+/// An entry is only reachable while its token is valid: once the value is
+/// removed from the store, its entry is ignored by this map, and replaced
+/// if the slot is reused by a new value.
+pub struct SparseSecondaryMap<T> {
+    entries: HashMap<usize, (AnyToken, T)>,
+}
+
+impl<T> SparseSecondaryMap<T> {
+    /// Create a new empty map
+    pub fn new() -> SparseSecondaryMap<T> {
+        SparseSecondaryMap {
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Associate a value to a token
+    ///
+    /// Returns the value previously associated to this token, if any.
+    /// Nothing is inserted if the token is no longer valid.
+    pub fn insert<K: Key>(&mut self, key: &K, value: T) -> Option<T> {
+        let token = key.any_token();
+        if !token.is_valid() {
+            return None;
+        }
+        match self.entries.insert(token.id, (token.clone(), value)) {
+            Some((old_token, old_value)) if old_token.generation == token.generation => Some(old_value),
+            _ => None,
+        }
+    }
+
+    /// Access the value associated to a token
+    pub fn get<K: Key>(&self, key: &K) -> Option<&T> {
+        let token = key.any_token();
+        match self.entries.get(&token.id) {
+            Some((entry_token, value)) if is_same(entry_token, &token) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Mutably access the value associated to a token
+    pub fn get_mut<K: Key>(&mut self, key: &K) -> Option<&mut T> {
+        let token = key.any_token();
+        match self.entries.get_mut(&token.id) {
+            Some((entry_token, value)) if is_same(entry_token, &token) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Check wether a value is associated to this token
+    pub fn contains_key<K: Key>(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Remove the value associated to a token
+    pub fn remove<K: Key>(&mut self, key: &K) -> Option<T> {
+        let token = key.any_token();
+        match self.entries.get(&token.id) {
+            Some((entry_token, _)) if is_same(entry_token, &token) => {}
+            _ => return None,
+        }
+        self.entries.remove(&token.id).map(|(_, value)| value)
+    }
+
+    /// Number of entries in this map
+    ///
+    /// This includes the entries whose token is no longer valid, until
+    /// they are cleaned up with `retain` or replaced.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check wether this map has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Keep only the entries with valid tokens for which `f` returns `true`
+    pub fn retain<F: FnMut(&AnyToken, &mut T) -> bool>(&mut self, mut f: F) {
+        self.entries
+            .retain(|_, (token, value)| token.is_valid() && f(token, value));
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Iterate over the entries with valid tokens
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            inner: self.entries.values(),
+        }
+    }
+
+    /// Mutably iterate over the entries with valid tokens
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            inner: self.entries.values_mut(),
+        }
+    }
+
+    /// Iterate over the tokens present in both this map and `other`
+    ///
+    /// This yields the values associated to each such token in both maps.
+    pub fn join<'a, U>(&'a self, other: &'a SparseSecondaryMap<U>) -> Join<'a, T, U> {
+        Join {
+            left: self,
+            right: other.iter(),
+        }
+    }
+
+    /// Iterate over the tokens present in both this map and `other`, with
+    /// mutable access to the values of `other`
+    pub fn join_mut<'a, U>(&'a self, other: &'a mut SparseSecondaryMap<U>) -> JoinMut<'a, T, U> {
+        JoinMut {
+            left: self,
+            right: other.iter_mut(),
+        }
+    }
+}
+
+fn is_same(a: &AnyToken, b: &AnyToken) -> bool {
+    a.generation == b.generation && a.is_valid()
+}
+
+impl<T> Default for SparseSecondaryMap<T> {
+    fn default() -> SparseSecondaryMap<T> {
+        SparseSecondaryMap::new()
+    }
+}
+
+/// Iterator over the entries of a `SparseSecondaryMap`
+pub struct Iter<'a, T: 'a> {
+    inner: hash_map::Values<'a, usize, (AnyToken, T)>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (&'a AnyToken, &'a T);
+    fn next(&mut self) -> Option<(&'a AnyToken, &'a T)> {
+        self.inner
+            .by_ref()
+            .find(|(token, _)| token.is_valid())
+            .map(|(token, value)| (token, value))
+    }
+}
+
+/// Mutable iterator over the entries of a `SparseSecondaryMap`
+pub struct IterMut<'a, T: 'a> {
+    inner: hash_map::ValuesMut<'a, usize, (AnyToken, T)>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = (&'a AnyToken, &'a mut T);
+    fn next(&mut self) -> Option<(&'a AnyToken, &'a mut T)> {
+        self.inner
+            .by_ref()
+            .find(|(token, _)| token.is_valid())
+            .map(|(token, value)| (&*token, value))
+    }
+}
+
+/// Iterator over the entries shared by two `SparseSecondaryMap`
+pub struct Join<'a, T: 'a, U: 'a> {
+    left: &'a SparseSecondaryMap<T>,
+    right: Iter<'a, U>,
+}
+
+impl<'a, T, U> Iterator for Join<'a, T, U> {
+    type Item = (&'a AnyToken, &'a T, &'a U);
+    fn next(&mut self) -> Option<(&'a AnyToken, &'a T, &'a U)> {
+        let left = self.left;
+        self.right
+            .by_ref()
+            .filter_map(|(token, right)| left.get(token).map(|left| (token, left, right)))
+            .next()
+    }
+}
+
+/// Iterator over the entries shared by two `SparseSecondaryMap`, with mutable
+/// access to the second one
+pub struct JoinMut<'a, T: 'a, U: 'a> {
+    left: &'a SparseSecondaryMap<T>,
+    right: IterMut<'a, U>,
+}
+
+impl<'a, T, U> Iterator for JoinMut<'a, T, U> {
+    type Item = (&'a AnyToken, &'a T, &'a mut U);
+    fn next(&mut self) -> Option<(&'a AnyToken, &'a T, &'a mut U)> {
+        let left = self.left;
+        self.right
+            .by_ref()
+            .filter_map(|(token, right)| left.get(token).map(|left| (token, left, right)))
+            .next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Store;
+
+    #[test]
+    fn attach_data() {
+        let mut store = Store::new();
+        let token1 = store.insert(42);
+        let token2 = store.insert("I like trains");
+        let mut map = SparseSecondaryMap::new();
+        assert_eq!(map.insert(&token1, 1.5), None);
+        assert_eq!(map.insert(&token1, 2.5), Some(1.5));
+        map.insert(&AnyToken::from(&token2), 3.5);
+        *map.get_mut(&token2).unwrap() += 1.0;
+        assert_eq!(map.get(&token2), Some(&4.5));
+        assert_eq!(map.iter().count(), 2);
+        store.remove(token1.clone());
+        assert_eq!(map.get(&token1), None);
+        assert_eq!(map.iter().count(), 1);
+        // the slot is reused by a new value, the old entry is not visible
+        let token3 = store.insert(12);
+        assert!(!map.contains_key(&token3));
+        map.retain(|_, _| true);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.remove(&token2), Some(4.5));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn join() {
+        let mut store = Store::new();
+        let tokens = store.insert_iter(0..4);
+        let mut positions = SparseSecondaryMap::new();
+        let mut velocities = SparseSecondaryMap::new();
+        for token in &tokens[..3] {
+            positions.insert(token, 0.0);
+        }
+        for token in &tokens[1..] {
+            velocities.insert(token, 1.0);
+        }
+        for (_, velocity, position) in velocities.join_mut(&mut positions) {
+            *position += *velocity;
+        }
+        assert_eq!(positions.get(&tokens[0]), Some(&0.0));
+        assert_eq!(positions.get(&tokens[1]), Some(&1.0));
+        assert_eq!(positions.join(&velocities).count(), 2);
+        store.remove(tokens[1].clone());
+        assert_eq!(positions.join(&velocities).count(), 1);
+    }
+}
@@ -0,0 +1,158 @@
+use super::Store;
+use std::any::Any;
+use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{channel, Sender};
+use std::thread;
+
+type Command = Box<dyn FnOnce(&mut Store) + Send>;
+
+/// A store owned by a dedicated thread
+///
+/// The store itself never leaves its thread: all operations are sent as
+/// commands over a channel and processed sequentially by this thread, which
+/// replies with their results. This gives multi-threaded programs a safe way
+/// to share a store without requiring it to be `Sync`.
+///
+/// As the values are sent to the store thread, they must be `Send`. Values are
+/// designated by `RemoteToken`s, which can be freely sent between threads.
+///
+/// This handle can be cloned to access the same store from several threads,
+/// the store thread exits once all handles have been dropped.
+///
+/// If an operation panics on the store thread, for example because a closure
+/// given to `with` panicked, the panic is caught and resumed on the thread
+/// that requested the operation. The store thread keeps serving the other
+/// handles, with the store left as the panicking operation left it.
+pub struct StoreService {
+    sender: Sender<Command>,
+}
+
+/// A token designating a value of a `StoreService`
+pub struct RemoteToken<V> {
+    handle: u64,
+    _type: PhantomData<V>,
+}
+
+impl<V> Clone for RemoteToken<V> {
+    fn clone(&self) -> RemoteToken<V> {
+        RemoteToken {
+            handle: self.handle,
+            _type: PhantomData,
+        }
+    }
+}
+
+impl StoreService {
+    /// Spawn a new store thread and return a handle to it
+    pub fn spawn() -> StoreService {
+        let (sender, receiver) = channel::<Command>();
+        thread::spawn(move || {
+            let mut store = Store::new();
+            for command in receiver {
+                command(&mut store);
+            }
+        });
+        StoreService { sender }
+    }
+
+    /// Insert a new value in the store
+    pub fn insert<V: Any + Send + 'static>(&self, value: V) -> RemoteToken<V> {
+        let handle = self.call(move |store| store.insert(value).to_handle());
+        RemoteToken {
+            handle,
+            _type: PhantomData,
+        }
+    }
+
+    /// Run a closure on a value of the store
+    ///
+    /// The closure is executed on the store thread. Returns `None` if the
+    /// value was removed.
+    pub fn with<V, T, F>(&self, token: &RemoteToken<V>, f: F) -> Option<T>
+    where
+        V: Any + Send + 'static,
+        T: Send + 'static,
+        F: FnOnce(&mut V) -> T + Send + 'static,
+    {
+        let handle = token.handle;
+        self.call(move |store| {
+            store
+                .token_from_handle::<V>(handle)
+                .map(|token| f(store.get_mut(&token)))
+        })
+    }
+
+    /// Remove a value from the store
+    ///
+    /// Returns `None` if the value was already removed.
+    pub fn remove<V: Any + Send + 'static>(&self, token: RemoteToken<V>) -> Option<V> {
+        let handle = token.handle;
+        self.call(move |store| {
+            store
+                .token_from_handle::<V>(handle)
+                .map(|token| store.remove(token))
+        })
+    }
+
+    fn call<T, F>(&self, f: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Store) -> T + Send + 'static,
+    {
+        let (sender, receiver) = channel();
+        let command = Box::new(move |store: &mut Store| {
+            let _ = sender.send(panic::catch_unwind(AssertUnwindSafe(|| f(store))));
+        });
+        if self.sender.send(command).is_err() {
+            panic!("Attempted to use a StoreService whose thread has stopped!");
+        }
+        match receiver.recv() {
+            Ok(Ok(ret)) => ret,
+            Ok(Err(payload)) => panic::resume_unwind(payload),
+            Err(_) => panic!("Attempted to use a StoreService whose thread has stopped!"),
+        }
+    }
+}
+
+impl Clone for StoreService {
+    fn clone(&self) -> StoreService {
+        StoreService {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_with_remove() {
+        let service = StoreService::spawn();
+        let token = service.insert(42);
+        let remote = service.clone();
+        let token2 = token.clone();
+        thread::spawn(move || {
+            remote.with(&token2, |v| *v += 1);
+        }).join()
+            .unwrap();
+        assert_eq!(service.with(&token, |v| *v), Some(43));
+        assert_eq!(service.remove(token.clone()), Some(43));
+        assert_eq!(service.with(&token, |v| *v), None);
+        assert_eq!(service.remove(token), None);
+    }
+
+    #[test]
+    fn survives_panics() {
+        let service = StoreService::spawn();
+        let token = service.insert(42);
+        let remote = service.clone();
+        let token2 = token.clone();
+        let result = thread::spawn(move || {
+            remote.with(&token2, |_: &mut i32| -> () { panic!("failed command") });
+        }).join();
+        assert!(result.is_err());
+        assert_eq!(service.with(&token, |v| *v), Some(42));
+    }
+}
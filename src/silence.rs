@@ -0,0 +1,92 @@
+use super::{Store, StoreEvent};
+
+// Ends a call to `silenced`, even if its closure panicked
+struct Silence<'a> {
+    store: &'a mut Store,
+    // the count of the enclosing call, if nested
+    previous: Option<usize>,
+}
+
+impl<'a> Drop for Silence<'a> {
+    fn drop(&mut self) {
+        let count = self.store.silenced.take().unwrap_or(0);
+        match self.previous {
+            Some(outer) => self.store.silenced = Some(outer + count),
+            None => {
+                if count > 0 {
+                    self.store.emit(StoreEvent::Bulk(count));
+                }
+                self.store.notify_silenced();
+            }
+        }
+    }
+}
+
+impl Store {
+    /// Run a closure without notifying the listeners of each mutation
+    ///
+    /// The events of the mutations done by `f` are not sent to the listeners
+    /// of `events`. Instead, a single `StoreEvent::Bulk` with their number is
+    /// sent once `f` returns, if there were any. This is meant for bulk
+    /// operations, like importing many values at once. The receivers of
+    /// `watch_type` likewise get a single `StoreEvent::Bulk`, with the number
+    /// of mutations of their type.
+    ///
+    /// Nested calls are merged into the outermost one. If `f` panics, the
+    /// events are still reported, and the store stops being silenced.
+    pub fn silenced<T, F: FnOnce(&mut Store) -> T>(&mut self, f: F) -> T {
+        let previous = self.silenced.replace(0);
+        let guard = Silence { store: self, previous };
+        f(&mut *guard.store)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn silenced() {
+        let mut store = Store::new();
+        let events = store.events();
+        let count = store.silenced(|store| {
+            let tokens = (0..100).map(|i| store.insert(i)).collect::<Vec<_>>();
+            store.silenced(|store| store.remove(tokens[0].clone()));
+            tokens.len()
+        });
+        assert_eq!(count, 100);
+        store.silenced(|_| ());
+        match events.try_recv() {
+            Ok(StoreEvent::Bulk(101)) => {}
+            other => panic!("unexpected event {:?}", other),
+        }
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn silenced_panic() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let mut store = Store::new();
+        let events = store.events();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            store.silenced(|store| {
+                store.insert(1);
+                store.silenced(|store| {
+                    store.insert(2);
+                    panic!("failed bulk operation");
+                })
+            })
+        }));
+        assert!(result.is_err());
+        store.insert(3);
+        match events.try_recv() {
+            Ok(StoreEvent::Bulk(2)) => {}
+            other => panic!("unexpected event {:?}", other),
+        }
+        match events.try_recv() {
+            Ok(StoreEvent::Inserted(_)) => {}
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use super::{Store, Token};
+use std::any::Any;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+
+// A boxed value, kept as a raw pointer
+//
+// Moving or reborrowing a `Box` asserts unique access to its allocation,
+// which invalidates the raw pointers previously derived from it. All the
+// accesses to a stored value instead go through this pointer, so that the
+// ones given out by `get_stable` stay usable.
+pub(crate) struct Stored(NonNull<dyn Any>);
+
+impl Stored {
+    pub(crate) fn new(value: Box<dyn Any>) -> Stored {
+        // a box is never null
+        Stored(unsafe { NonNull::new_unchecked(Box::into_raw(value)) })
+    }
+
+    pub(crate) fn into_box(self) -> Box<dyn Any> {
+        let ptr = self.0;
+        ::std::mem::forget(self);
+        // the pointer was created by `Box::into_raw`, and is owned by `self`
+        unsafe { Box::from_raw(ptr.as_ptr()) }
+    }
+
+    fn as_ptr(&self) -> *mut dyn Any {
+        self.0.as_ptr()
+    }
+}
+
+impl Deref for Stored {
+    type Target = dyn Any;
+    fn deref(&self) -> &dyn Any {
+        unsafe { &*self.0.as_ptr() }
+    }
+}
+
+impl DerefMut for Stored {
+    fn deref_mut(&mut self) -> &mut dyn Any {
+        unsafe { &mut *self.0.as_ptr() }
+    }
+}
+
+impl Drop for Stored {
+    fn drop(&mut self) {
+        drop(unsafe { Box::from_raw(self.0.as_ptr()) });
+    }
+}
+
+/// A pointer to a stored value which stays valid until its removal
+///
+/// Values are boxed by the store, so their address never changes while
+/// they are stored, even if the store grows or other values are removed.
+/// A `StableRef` keeps this address along with the token of the value,
+/// so that it can be handed to foreign code and revalidated cheaply.
+///
+/// The address stays the same, and the pointer usable, across insertions and
+/// removals of other values, `replace`, `soft_remove` followed by `undelete`,
+/// and accesses through the token, as the store never moves or reborrows the
+/// box of a value after its insertion. It becomes dangling once the value is
+/// removed, purged, migrated to another type, or the store is dropped.
+///
+/// It is created by `Store::get_stable`.
+pub struct StableRef<V> {
+    ptr: *mut V,
+    token: Token<V>,
+}
+
+impl<V> StableRef<V> {
+    /// The address of the value
+    ///
+    /// It may only be dereferenced while `is_valid` returns `true`, and
+    /// while the store is not otherwise borrowed in a conflicting way.
+    pub fn as_ptr(&self) -> *mut V {
+        self.ptr
+    }
+
+    /// Check wether the value is still in its store
+    ///
+    /// Like `Token::is_valid`, this does not need the store.
+    pub fn is_valid(&self) -> bool {
+        self.token.is_valid()
+    }
+
+    /// The token of the value
+    pub fn token(&self) -> &Token<V> {
+        &self.token
+    }
+}
+
+impl<V> Clone for StableRef<V> {
+    fn clone(&self) -> StableRef<V> {
+        StableRef {
+            ptr: self.ptr,
+            token: self.token.clone(),
+        }
+    }
+}
+
+impl Store {
+    /// Get a stable pointer to a value previously inserted in this store
+    ///
+    /// The pointer remains valid until the value is removed, and in
+    /// particular across insertions and removals of other values.
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn get_stable<V: Any + 'static>(&mut self, token: &Token<V>) -> StableRef<V> {
+        // checks the token and records a mutable access
+        self.get_mut(token);
+        let stored = self.values[token.id].value.as_ref().unwrap();
+        StableRef {
+            ptr: stored.as_ptr() as *mut V,
+            token: token.clone(),
+        }
+    }
+
+    /// Access a value through a stable pointer, after revalidating it
+    ///
+    /// Returns `None` if its value was removed from this store.
+    pub fn get_by_stable<V: Any + 'static>(&self, stable: &StableRef<V>) -> Option<&V> {
+        if self.contains(&stable.token) {
+            // the value is still stored, so its address did not change
+            Some(unsafe { &*stable.ptr })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_address() {
+        let mut store = Store::new();
+        let token = store.insert(42u64);
+        let stable = store.get_stable(&token);
+        for i in 0..1000u64 {
+            store.insert(i);
+        }
+        assert!(stable.is_valid());
+        assert_eq!(stable.as_ptr() as *const u64, store.get(&token) as *const u64);
+        assert_eq!(store.get_by_stable(&stable), Some(&42));
+        store.remove(token);
+        assert!(!stable.is_valid());
+        assert_eq!(store.get_by_stable(&stable), None);
+    }
+
+    #[test]
+    fn stable_soft_remove() {
+        let mut store = Store::new();
+        let token = store.insert(String::from("text"));
+        let stable = store.get_stable(&token);
+        store.soft_remove(&token);
+        assert_eq!(store.get_by_stable(&stable), None);
+        assert!(store.undelete(&token));
+        store.replace(&token, String::from("other"));
+        store.get_mut(&token).push('!');
+        unsafe { (*stable.as_ptr()).push('?') };
+        assert_eq!(store.get_by_stable(&stable).map(|s| &s[..]), Some("other!?"));
+        assert_eq!(store.get(&token), "other!?");
+    }
+}
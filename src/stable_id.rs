@@ -0,0 +1,95 @@
+use super::{Key, Store, Token};
+use std::any::Any;
+
+impl Store {
+    /// Give a value a stable identifier
+    ///
+    /// Unlike slot indices, stable identifiers are chosen by the user, and
+    /// are kept when the value is migrated with `migrate`, moved by
+    /// `partition_by_type` or copied by `clone_subset`, so they can be
+    /// stored in documents. A value has
+    /// at most one stable identifier, this replaces any previous one.
+    ///
+    /// Panics if the value was removed from this store, or if the identifier
+    /// is already used by another value.
+    pub fn set_stable_id<K: Key>(&mut self, key: &K, stable_id: u64) {
+        let token = key.any_token();
+        if !self.is_alive(&token) {
+            panic!("Attempted to set the stable id of a value that was already removed!");
+        }
+        match self.stable_ids.get(&stable_id) {
+            Some(&id) if id != token.id => {
+                panic!("Attempted to set a stable id already used by another value!")
+            }
+            _ => {}
+        }
+        self.assign_stable_id(token.id, stable_id);
+    }
+
+    /// The stable identifier of a value, if it has one
+    ///
+    /// Returns `None` if the value was removed from this store.
+    pub fn stable_id<K: Key>(&self, key: &K) -> Option<u64> {
+        let token = key.any_token();
+        if self.is_alive(&token) {
+            self.values[token.id].stable_id
+        } else {
+            None
+        }
+    }
+
+    /// Get the token of the value with a given stable identifier
+    ///
+    /// Returns `None` if no value of type `V` has this identifier.
+    pub fn token_by_stable_id<V: Any + 'static>(&self, stable_id: u64) -> Option<Token<V>> {
+        let id = *self.stable_ids.get(&stable_id)?;
+        let generation = self.values[id].generation;
+        self.token_from_raw_parts(id as u32, generation)
+    }
+
+    // Sets the stable id of an occupied slot
+    pub(crate) fn assign_stable_id(&mut self, id: usize, stable_id: u64) {
+        if let Some(previous) = self.values[id].stable_id.take() {
+            self.stable_ids.remove(&previous);
+        }
+        self.values[id].stable_id = Some(stable_id);
+        self.stable_ids.insert(stable_id, id);
+    }
+
+    // Removes the stable id of a slot, returning it
+    pub(crate) fn clear_stable_id(&mut self, id: usize) -> Option<u64> {
+        let stable_id = self.values[id].stable_id.take()?;
+        self.stable_ids.remove(&stable_id);
+        Some(stable_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_ids() {
+        let mut store = Store::new();
+        let token = store.insert(42);
+        store.set_stable_id(&token, 0xdead_beef);
+        assert_eq!(store.stable_id(&token), Some(0xdead_beef));
+        let migrated = store.migrate(&token, |v| v.to_string());
+        assert_eq!(store.stable_id(&migrated), Some(0xdead_beef));
+        assert!(store.token_by_stable_id::<i32>(0xdead_beef).is_none());
+        let found = store.token_by_stable_id::<String>(0xdead_beef).unwrap();
+        assert_eq!(store.get(&found), "42");
+        store.remove(found);
+        assert!(store.token_by_stable_id::<String>(0xdead_beef).is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn duplicate_stable_id() {
+        let mut store = Store::new();
+        let first = store.insert(1);
+        let second = store.insert(2);
+        store.set_stable_id(&first, 1);
+        store.set_stable_id(&second, 1);
+    }
+}
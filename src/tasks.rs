@@ -0,0 +1,156 @@
+use super::{AnyToken, Slot, Store, Token};
+use std::any::{Any, TypeId};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+// Polls a boxed `Task<T>` if it is pending, returning wether it completed
+pub(crate) type PollFn = fn(&mut dyn Any, &mut Context) -> bool;
+
+fn poll_task<T: 'static>(task: &mut dyn Any, cx: &mut Context) -> bool {
+    let task = task.downcast_mut::<Task<T>>().unwrap();
+    let output = match task.state {
+        TaskState::Pending(ref mut future) => match future.as_mut().poll(cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => return false,
+        },
+        TaskState::Ready(_) => return false,
+    };
+    task.state = TaskState::Ready(output);
+    true
+}
+
+enum TaskState<T> {
+    Pending(Pin<Box<dyn Future<Output = T>>>),
+    Ready(T),
+}
+
+/// A future stored in a store, or its output once it completed
+///
+/// It is stored by `Store::insert_future`, and driven by
+/// `Store::poll_futures`.
+pub struct Task<T> {
+    state: TaskState<T>,
+}
+
+impl<T> Task<T> {
+    /// Check wether the future completed
+    pub fn is_ready(&self) -> bool {
+        match self.state {
+            TaskState::Ready(_) => true,
+            TaskState::Pending(_) => false,
+        }
+    }
+
+    /// The output of the future, if it completed
+    pub fn output(&self) -> Option<&T> {
+        match self.state {
+            TaskState::Ready(ref output) => Some(output),
+            TaskState::Pending(_) => None,
+        }
+    }
+}
+
+impl Store {
+    /// Insert a future, to be driven by `poll_futures`
+    ///
+    /// Once it completes, its output is kept in its slot, until it is taken
+    /// with `take_output`.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn insert_future<F>(&mut self, future: F) -> Token<Task<F::Output>>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        self.pollers.insert(TypeId::of::<Task<F::Output>>(), poll_task::<F::Output>);
+        self.insert(Task {
+            state: TaskState::Pending(Box::pin(future)),
+        })
+    }
+
+    /// Poll all the pending futures of this store
+    ///
+    /// Returns the tokens of the futures that completed during this call, in
+    /// slot order.
+    pub fn poll_futures(&mut self, cx: &mut Context) -> Vec<AnyToken> {
+        let mut completed = Vec::new();
+        if self.pollers.is_empty() {
+            return completed;
+        }
+        for id in 0..self.values.len() {
+            let poll = match self.values[id] {
+                Slot {
+                    type_id,
+                    value: Some(_),
+                    ..
+                } => match self.pollers.get(&type_id) {
+                    Some(&poll) => poll,
+                    None => continue,
+                },
+                _ => continue,
+            };
+            let done = {
+                let boxed = self.values[id].value.as_mut().unwrap();
+                poll(&mut **boxed, cx)
+            };
+            if done {
+                self.values[id].version += 1;
+                completed.push(self.slot_token(id).unwrap());
+            }
+        }
+        completed
+    }
+
+    /// Remove a completed future from this store, returning its output
+    ///
+    /// If it did not complete yet, the store is left untouched and the token
+    /// is given back as an error.
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn take_output<T: 'static>(&mut self, token: Token<Task<T>>) -> Result<T, Token<Task<T>>> {
+        if !self.get(&token).is_ready() {
+            return Err(token);
+        }
+        match self.remove(token).state {
+            TaskState::Ready(output) => Ok(output),
+            TaskState::Pending(_) => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Waker;
+
+    struct CountDown(u32);
+
+    impl Future for CountDown {
+        type Output = &'static str;
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<&'static str> {
+            if self.0 == 0 {
+                Poll::Ready("done")
+            } else {
+                self.0 -= 1;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    #[test]
+    fn futures() {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut store = Store::new();
+        let quick = store.insert_future(CountDown(0));
+        let slow = store.insert_future(CountDown(1));
+        assert_eq!(store.poll_futures(&mut cx), vec![AnyToken::from(&quick)]);
+        let slow = store.take_output(slow).unwrap_err();
+        assert_eq!(store.poll_futures(&mut cx), vec![AnyToken::from(&slow)]);
+        assert!(store.poll_futures(&mut cx).is_empty());
+        assert_eq!(store.get(&quick).output(), Some(&"done"));
+        assert_eq!(store.take_output(slow), Ok("done"));
+        assert_eq!(store.len(), 1);
+    }
+}
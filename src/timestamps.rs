@@ -0,0 +1,119 @@
+use super::{AnyToken, Store, Token};
+use std::any::Any;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// When a value was inserted and last accessed
+pub(crate) struct Times {
+    created: Instant,
+    accessed: Cell<Instant>,
+}
+
+impl Times {
+    pub(crate) fn new() -> Times {
+        let now = Instant::now();
+        Times {
+            created: now,
+            accessed: Cell::new(now),
+        }
+    }
+}
+
+// The times of each occupied slot, if timestamps are enabled
+pub(crate) type Timestamps = Option<HashMap<usize, Times>>;
+
+impl Store {
+    /// Start tracking when values are inserted and last accessed
+    ///
+    /// The values already in the store are considered inserted and accessed
+    /// now. Accesses are the calls to `get`, `get_mut`, and their `try_` and
+    /// `_unchecked` variants.
+    pub fn enable_timestamps(&mut self) {
+        if self.timestamps.is_some() {
+            return;
+        }
+        let times = (0..self.values.len())
+            .filter_map(|id| self.slot_token(id))
+            .map(|token| (token.id, Times::new()))
+            .collect();
+        self.timestamps = Some(times);
+    }
+
+    /// Time elapsed since a value was inserted
+    ///
+    /// Returns `None` if timestamps are not enabled. Panics if the provided
+    /// token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn age<V: Any + 'static>(&self, token: &Token<V>) -> Option<Duration> {
+        self.times(token).map(|times| times.created.elapsed())
+    }
+
+    /// Time elapsed since a value was last accessed
+    ///
+    /// Returns `None` if timestamps are not enabled. Panics if the provided
+    /// token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn idle_time<V: Any + 'static>(&self, token: &Token<V>) -> Option<Duration> {
+        self.times(token).map(|times| times.accessed.get().elapsed())
+    }
+
+    /// The values of this store with their idle time, most idle first
+    ///
+    /// Returns an empty list if timestamps are not enabled.
+    pub fn by_idleness(&self) -> Vec<(AnyToken, Duration)> {
+        let mut idle = match self.timestamps {
+            Some(ref timestamps) => timestamps
+                .iter()
+                .filter_map(|(&id, times)| Some((self.slot_token(id)?, times.accessed.get().elapsed())))
+                .collect::<Vec<_>>(),
+            None => Vec::new(),
+        };
+        idle.sort_by_key(|&(_, idle)| ::std::cmp::Reverse(idle));
+        idle
+    }
+
+    // The times of a value, without counting as an access
+    #[cfg_attr(feature = "provenance", track_caller)]
+    fn times<V: Any + 'static>(&self, token: &Token<V>) -> Option<&Times> {
+        let valid = match self.values.get(token.id) {
+            Some(slot) => slot.generation == token.generation && slot.live.is_some() && slot.value.is_some(),
+            None => false,
+        };
+        if !valid {
+            self.invalid_access(token);
+        }
+        self.timestamps.as_ref()?.get(&token.id)
+    }
+
+    // Records an access to the value of a slot
+    pub(crate) fn touch(&self, id: usize) {
+        if let Some(times) = self.timestamps.as_ref().and_then(|timestamps| timestamps.get(&id)) {
+            times.accessed.set(Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+
+    #[test]
+    fn timestamps() {
+        let mut store = Store::new();
+        let old = store.insert(1);
+        assert_eq!(store.age(&old), None);
+        store.enable_timestamps();
+        let new = store.insert(2);
+        sleep(Duration::from_millis(10));
+        store.get(&new);
+        assert!(store.age(&old).unwrap() >= Duration::from_millis(10));
+        assert!(store.idle_time(&new).unwrap() < store.idle_time(&old).unwrap());
+        let idle = store.by_idleness();
+        assert_eq!(idle.len(), 2);
+        assert_eq!(idle[0].0, AnyToken::from(&old));
+        store.remove(old);
+        assert_eq!(store.by_idleness().len(), 1);
+    }
+}
@@ -0,0 +1,149 @@
+use super::{Store, StoreEvent, Stored, Token};
+use std::any::{type_name, Any};
+use std::collections::HashMap;
+
+// The values hidden by `soft_remove`, by slot
+pub(crate) type Tombstones = HashMap<usize, Stored>;
+
+impl Store {
+    /// Hide a value, keeping it recoverable until the next `purge`
+    ///
+    /// The value stays in the store, but accessing it fails as if it was
+    /// removed, and it is skipped by iterations, until it is restored by
+    /// `undelete`. Its tokens remain valid, so they can be used to restore it.
+    /// Hidden values are left out of `len`, of the quotas and of the indexes,
+    /// and their hiding is reported to the listeners as a removal.
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn soft_remove<V: Any + 'static>(&mut self, token: &Token<V>) {
+        if self.lookup::<V>(token.id, token.generation).is_none() {
+            self.invalid_access(token);
+        }
+        let event = StoreEvent::Removed(self.slot_token(token.id).unwrap());
+        if !self.indexes.is_empty() {
+            self.index_remove(token.id);
+        }
+        let value = self.values[token.id].value.take().unwrap();
+        self.tombstones.insert(token.id, value);
+        self.count_quota(self.values[token.id].type_id, false);
+        self.len -= 1;
+        self.emit(event);
+    }
+
+    /// Restore a value hidden by `soft_remove`
+    ///
+    /// Its restoration is reported to the listeners as an insertion. Returns
+    /// `false` if this value was not hidden, for example because it was
+    /// purged since, or if the quota of `V` was reached in the meantime.
+    pub fn undelete<V: Any + 'static>(&mut self, token: &Token<V>) -> bool {
+        if !self.is_soft_removed(token) || self.quota_reached(self.values[token.id].type_id, type_name::<V>()) {
+            return false;
+        }
+        // the slot still holds its liveness cell, so it is only missing its value
+        self.restore_tombstone(token.id);
+        let event = StoreEvent::Inserted(self.slot_token(token.id).unwrap());
+        self.emit(event);
+        true
+    }
+
+    /// Check wether a value is hidden by `soft_remove`
+    pub fn is_soft_removed<V: Any + 'static>(&self, token: &Token<V>) -> bool {
+        token.is_valid()
+            && self.tombstones.contains_key(&token.id)
+            && self.values[token.id].generation == token.generation
+            && self.values[token.id]
+                .live
+                .as_ref()
+                .is_some_and(|live| live.ptr_eq(&token.live))
+    }
+
+    /// Remove for good all the values hidden by `soft_remove`
+    ///
+    /// Their tokens are invalidated. No event is sent, as their removal was
+    /// already reported by `soft_remove`. Returns the number of purged values.
+    pub fn purge(&mut self) -> usize {
+        let mut ids = self.tombstones.keys().cloned().collect::<Vec<_>>();
+        ids.sort_unstable();
+        for &id in &ids {
+            self.purge_at(id);
+        }
+        ids.len()
+    }
+
+    // Removes for good the hidden value of a slot, without reporting it
+    pub(crate) fn purge_at(&mut self, id: usize) {
+        self.restore_tombstone(id);
+        self.finalize_slot(id, false);
+    }
+
+    // Puts a hidden value back in its slot
+    pub(crate) fn restore_tombstone(&mut self, id: usize) {
+        let value = self.tombstones.remove(&id).unwrap();
+        self.values[id].value = Some(value);
+        self.count_quota(self.values[id].type_id, true);
+        self.len += 1;
+        if !self.indexes.is_empty() {
+            self.index_insert(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soft_remove() {
+        let mut store = Store::new();
+        let first = store.insert(1);
+        let second = store.insert(2);
+        store.soft_remove(&first);
+        store.soft_remove(&second);
+        assert!(!store.contains(&first));
+        assert!(store.try_get(&first).is_err());
+        assert_eq!(store.len(), 0);
+        assert_eq!(store.iter().count(), 0);
+        assert!(store.undelete(&first));
+        assert_eq!(*store.get(&first), 1);
+        assert_eq!(store.purge(), 1);
+        assert!(!second.is_valid());
+        assert!(!store.undelete(&second));
+        assert_eq!(store.len(), 1);
+        assert!(store.debug_validate().is_ok());
+    }
+
+    #[test]
+    fn soft_remove_bookkeeping() {
+        let mut store = Store::new();
+        let events = store.events();
+        store.set_quota::<i32>(Some(1));
+        store.index_by::<i32, _, _>(|&v| v);
+        let token = store.insert(1);
+        store.soft_remove(&token);
+        assert!(store.tokens_where::<i32, _>(&1).is_empty());
+        let other = store.insert(2);
+        assert!(!store.undelete(&token));
+        store.remove(other);
+        assert!(store.undelete(&token));
+        assert_eq!(store.tokens_where::<i32, _>(&1), vec![token.clone()]);
+        let mut removals = 0;
+        let mut insertions = 0;
+        for event in events.try_iter() {
+            match event {
+                StoreEvent::Inserted(_) => insertions += 1,
+                StoreEvent::Removed(_) => removals += 1,
+                _ => panic!("Expected only insertions and removals."),
+            }
+        }
+        assert_eq!((insertions, removals), (3, 2));
+        store.soft_remove(&token);
+        events.try_recv().unwrap();
+        assert_eq!(store.purge(), 1);
+        assert!(events.try_recv().is_err());
+        let mut foreign = Store::new();
+        let stranger = foreign.insert(3);
+        foreign.soft_remove(&stranger);
+        assert!(!store.is_soft_removed(&stranger));
+    }
+}
@@ -0,0 +1,137 @@
+use super::{Slot, Store};
+use std::collections::HashSet;
+use std::fmt;
+
+/// The result of checking the internal invariants of a store
+///
+/// It is created by `Store::debug_validate`.
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    /// Total number of slots of the store
+    pub slots: usize,
+    /// Number of slots holding a value
+    pub occupied: usize,
+    /// Number of slots reserved by `reserve_tokens` and not filled yet
+    pub reserved: usize,
+    /// Number of vacant slots
+    pub vacant: usize,
+    /// Description of every broken invariant
+    pub problems: Vec<String>,
+}
+
+impl ValidationReport {
+    /// Check wether no broken invariant was found
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} slots: {} occupied, {} reserved, {} vacant",
+            self.slots, self.occupied, self.reserved, self.vacant
+        )?;
+        for problem in &self.problems {
+            writeln!(f, "- {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl Store {
+    /// Check the internal invariants of this store
+    ///
+    /// This walks the whole store, so it is meant for debugging and bug
+    /// reports rather than for routine use. Any inconsistency between the
+    /// slots, their liveness cells and the free list is listed in the
+    /// returned report.
+    pub fn debug_validate(&self) -> ValidationReport {
+        let mut report = ValidationReport {
+            slots: self.values.len(),
+            ..ValidationReport::default()
+        };
+        let mut cells = HashSet::new();
+        for id in 0..self.values.len() {
+            match self.values[id] {
+                Slot { live: None, value: None, .. } => report.vacant += 1,
+                Slot { live: None, value: Some(_), .. } => {
+                    report.problems.push(format!("slot {} holds a value but has no liveness cell", id));
+                }
+                Slot { live: Some(ref live), ref value, type_id, .. } => {
+                    match *value {
+                        Some(ref boxed) => {
+                            report.occupied += 1;
+                            if (**boxed).type_id() != type_id {
+                                report.problems.push(format!("slot {} holds a value of the wrong type", id));
+                            }
+                        }
+                        None => report.reserved += 1,
+                    }
+                    if !live.get() {
+                        report.problems.push(format!("slot {} is in use but its liveness cell is unset", id));
+                    }
+                    if !cells.insert(&*live.0 as *const _) {
+                        report.problems.push(format!("slot {} shares its liveness cell with another slot", id));
+                    }
+                }
+            }
+        }
+        if report.occupied != self.len {
+            report
+                .problems
+                .push(format!("the store counts {} values but holds {}", self.len, report.occupied));
+        }
+        let mut free = HashSet::new();
+        for &id in &self.free {
+            if !free.insert(id) {
+                report.problems.push(format!("slot {} appears twice in the free list", id));
+            }
+            match self.values.get(id) {
+                Some(&Slot { live: None, .. }) => {}
+                Some(_) => report.problems.push(format!("slot {} is in the free list but in use", id)),
+                None => report.problems.push(format!("slot {} is in the free list but does not exist", id)),
+            }
+        }
+        if free.len() + self.retired.len() != report.vacant {
+            report.problems.push(format!(
+                "{} slots are vacant but {} are in the free list and {} are retired",
+                report.vacant,
+                free.len(),
+                self.retired.len()
+            ));
+        }
+        for live in &self.cell_pool {
+            if live.get() {
+                report.problems.push("a pooled liveness cell is set".into());
+            }
+            if cells.contains(&(&*live.0 as *const _)) {
+                report.problems.push("a pooled liveness cell is used by a slot".into());
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        let mut store = Store::new();
+        let token = store.insert(1);
+        store.insert("I like trains");
+        store.reserve_tokens::<u8>(2);
+        store.remove(token);
+        let report = store.debug_validate();
+        assert!(report.is_ok(), "{}", report);
+        assert_eq!((report.slots, report.occupied, report.reserved, report.vacant), (4, 1, 2, 1));
+        // corrupt the store
+        store.len = 3;
+        store.free.push_back(1);
+        let report = store.debug_validate();
+        assert_eq!(report.problems.len(), 3, "{}", report);
+    }
+}
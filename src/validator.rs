@@ -0,0 +1,135 @@
+use super::Store;
+use std::any::{type_name, Any, TypeId};
+use std::fmt::Debug;
+
+// Checks a value, describing why it is rejected
+pub(crate) type ValidatorFn = Box<dyn Fn(&dyn Any) -> Result<(), String>>;
+
+impl Store {
+    /// Set a validator for the values of type `V`
+    ///
+    /// It is run on every value of type `V` about to be stored, whether it is
+    /// inserted, filled in, imported, migrated to `V` or given as a
+    /// replacement. If it returns an error, the fallible methods like
+    /// `try_insert` and `insert_with_id` give the value back, and the other
+    /// ones panic. Values already in the store are not checked, and neither
+    /// are the changes made through `get_mut`. Setting a new validator
+    /// replaces the previous one.
+    pub fn set_validator<V, E, F>(&mut self, validator: F)
+    where
+        V: Any + 'static,
+        E: Debug,
+        F: Fn(&V) -> Result<(), E> + 'static,
+    {
+        let validator = move |value: &dyn Any| validator(value.downcast_ref().unwrap()).map_err(|e| format!("{:?}", e));
+        self.validators.insert(TypeId::of::<V>(), Box::new(validator));
+    }
+
+    /// Remove the validator of the values of type `V`
+    pub fn clear_validator<V: Any + 'static>(&mut self) {
+        self.validators.remove(&TypeId::of::<V>());
+    }
+
+    // Runs the validator of `V` on a value, if any
+    pub(crate) fn check_value<V: Any + 'static>(&self, value: &V) -> Result<(), String> {
+        self.check_any(value)
+    }
+
+    // Runs the validator of the type of a type-erased value, if any
+    pub(crate) fn check_any(&self, value: &dyn Any) -> Result<(), String> {
+        match self.validators.get(&value.type_id()) {
+            Some(validator) => validator(value),
+            None => Ok(()),
+        }
+    }
+
+    // Panics if a value is rejected by the validator of its type
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub(crate) fn assert_valid<V: Any + 'static>(&self, value: &V) {
+        if let Err(error) = self.check_value(value) {
+            panic!("Attempted to store an invalid value of type {}: {}", type_name::<V>(), error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positive(value: &i32) -> Result<(), &'static str> {
+        if *value > 0 {
+            Ok(())
+        } else {
+            Err("not positive")
+        }
+    }
+
+    #[test]
+    fn validator() {
+        let mut store = Store::new();
+        store.set_validator(positive);
+        let token = store.insert(1);
+        assert_eq!(store.try_insert(-1), Err(-1));
+        assert_eq!(store.insert_with_id(4, 0).err(), Some(0));
+        assert_eq!(store.replace(&token, 2), 1);
+        store.clear_validator::<i32>();
+        store.insert(-1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempted to store an invalid value of type i32: \"not positive\"")]
+    fn invalid_replace() {
+        let mut store = Store::new();
+        let token = store.insert(1);
+        store.set_validator(positive);
+        store.replace(&token, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempted to store an invalid value of type i32")]
+    fn invalid_fill() {
+        let mut store = Store::new();
+        store.set_validator(positive);
+        let token = store.reserve_tokens::<i32>(1).pop().unwrap();
+        store.fill(&token, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempted to store an invalid value of type i32")]
+    fn invalid_migrate() {
+        let mut store = Store::new();
+        store.set_validator(positive);
+        let token = store.insert(1u8);
+        store.migrate(&token, |_| 0i32);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempted to store an invalid value of type i32")]
+    fn invalid_recycled() {
+        let mut store = Store::new();
+        store.set_validator(positive);
+        store.insert_recycled(|v: &mut i32| *v = -1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempted to store an invalid value of type i32")]
+    fn invalid_replace_current() {
+        let mut store = Store::new();
+        store.insert(1);
+        store.set_validator(positive);
+        let mut cursor = store.cursor();
+        cursor.next();
+        cursor.replace_current(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempted to store an invalid value of type <imported>")]
+    fn invalid_import() {
+        let mut source = Store::new();
+        source.insert(0);
+        let values = source.export();
+        let mut store = Store::new();
+        store.set_validator(positive);
+        store.import(values);
+    }
+}
@@ -0,0 +1,61 @@
+use super::{Store, Token};
+use std::any::Any;
+
+/// The version of a value in a store
+///
+/// The version of a value changes every time it is mutably accessed, be it
+/// with `get_mut`, `replace`, `with_value` or a cursor. It can be obtained
+/// with `Store::get_versioned`, and later used with `Store::compare_and_set`
+/// to only replace the value if it was not modified in the meantime.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Version(u64);
+
+impl Store {
+    /// Access a value along with its current version
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn get_versioned<V: Any + 'static>(&self, token: &Token<V>) -> (&V, Version) {
+        let value = self.get(token);
+        (value, Version(self.values[token.id].version))
+    }
+
+    /// Replace a value only if it was not modified since the given version
+    ///
+    /// If the value is still at version `expected`, it is replaced by `value`
+    /// and the old value is returned. Otherwise the store is left untouched
+    /// and `value` is given back as an error.
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn compare_and_set<V: Any + 'static>(&mut self, token: &Token<V>, expected: Version, value: V)
+                                             -> Result<V, V> {
+        if !self.contains(token) {
+            self.invalid_access(token);
+        }
+        if self.values[token.id].version != expected.0 {
+            return Err(value);
+        }
+        Ok(self.replace(token, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_and_set() {
+        let mut store = Store::new();
+        let token = store.insert(1);
+        let (_, version) = store.get_versioned(&token);
+        assert_eq!(store.compare_and_set(&token, version, 2), Ok(1));
+        // the value was modified by the previous call
+        assert_eq!(store.compare_and_set(&token, version, 3), Err(3));
+        let (&value, version) = store.get_versioned(&token);
+        assert_eq!(value, 2);
+        *store.get_mut(&token) += 1;
+        assert_eq!(store.compare_and_set(&token, version, 4), Err(4));
+        assert_eq!(*store.get(&token), 3);
+    }
+}
@@ -0,0 +1,102 @@
+use super::{Store, StoreEvent};
+use std::any::{Any, TypeId};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+// The type of the values and the sender of each `watch_type` receiver
+pub(crate) type TypeWatchers = Vec<(TypeId, Sender<StoreEvent>)>;
+
+impl Store {
+    /// Subscribe to the mutations of the values of type `V`
+    ///
+    /// This is like `events`, but only the insertions, removals and
+    /// replacements of values of type `V` are reported. The mutations made
+    /// in a call to `silenced` are reported all at once by a
+    /// `StoreEvent::Bulk`, with the number of mutations of values of type
+    /// `V`.
+    ///
+    /// The receiver can be dropped at any time to unsubscribe.
+    pub fn watch_type<V: Any + 'static>(&mut self) -> Receiver<StoreEvent> {
+        let (sender, receiver) = channel();
+        self.type_watchers.push((TypeId::of::<V>(), sender));
+        receiver
+    }
+
+    // Reports an event to the watchers of the type of its value
+    pub(crate) fn notify_type_watchers(&mut self, event: &StoreEvent) {
+        if let Some(type_id) = self.event_type(event) {
+            self.send_to_watchers(type_id, event);
+        }
+    }
+
+    // Counts an event suppressed by `silenced` for the watchers of its type
+    pub(crate) fn count_silenced(&mut self, event: &StoreEvent) {
+        if let Some(type_id) = self.event_type(event) {
+            if self.type_watchers.iter().any(|&(watched, _)| watched == type_id) {
+                *self.silenced_types.entry(type_id).or_insert(0) += 1;
+            }
+        }
+    }
+
+    // Reports the events suppressed by `silenced` as one bulk event per type
+    pub(crate) fn notify_silenced(&mut self) {
+        for (type_id, count) in ::std::mem::take(&mut self.silenced_types) {
+            self.send_to_watchers(type_id, &StoreEvent::Bulk(count));
+        }
+    }
+
+    // The type of the value an event is about, if any
+    fn event_type(&self, event: &StoreEvent) -> Option<TypeId> {
+        match *event {
+            StoreEvent::Inserted(ref token) | StoreEvent::Removed(ref token) | StoreEvent::Replaced(ref token) => {
+                Some(self.values[token.id].type_id)
+            }
+            StoreEvent::Bulk(_) => None,
+        }
+    }
+
+    fn send_to_watchers(&mut self, type_id: TypeId, event: &StoreEvent) {
+        self.type_watchers
+            .retain(|&(watched, ref watcher)| watched != type_id || watcher.send(event.clone()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use AnyToken;
+
+    #[test]
+    fn watch_type() {
+        let mut store = Store::new();
+        let events = store.watch_type::<String>();
+        store.insert(1);
+        let text = store.insert(String::from("text"));
+        store.replace(&text, String::new());
+        store.remove(text.clone());
+        for _ in 0..3 {
+            match events.try_recv() {
+                Ok(StoreEvent::Inserted(token)) | Ok(StoreEvent::Replaced(token)) | Ok(StoreEvent::Removed(token)) => {
+                    assert_eq!(token, AnyToken::from(&text))
+                }
+                _ => panic!("Expected an event about the string."),
+            }
+        }
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn watch_silenced() {
+        let mut store = Store::new();
+        let events = store.watch_type::<String>();
+        store.silenced(|store| {
+            store.insert(1);
+            let text = store.insert(String::new());
+            store.replace(&text, String::from("text"));
+        });
+        match events.try_recv() {
+            Ok(StoreEvent::Bulk(2)) => {}
+            _ => panic!("Expected a bulk event for the string."),
+        }
+        assert!(events.try_recv().is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use super::{Slot, Store, Token};
+use std::any::{Any, TypeId};
+use std::rc::{Rc, Weak};
+
+// Checks wether a boxed `Weak<T>` no longer has a referent
+pub(crate) type DeadFn = fn(&dyn Any) -> bool;
+
+fn weak_is_dead<T: 'static>(value: &dyn Any) -> bool {
+    value.downcast_ref::<Weak<T>>().unwrap().strong_count() == 0
+}
+
+impl Store {
+    /// Insert a weak reference to a shared value
+    ///
+    /// The store does not keep the value alive. Once it is dropped, the slot
+    /// is removed by the next `get_weak` or `prune`.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn insert_weak<T: 'static>(&mut self, value: &Rc<T>) -> Token<Weak<T>> {
+        self.weak_types.insert(TypeId::of::<Weak<T>>(), weak_is_dead::<T>);
+        self.insert(Rc::downgrade(value))
+    }
+
+    /// Get the value of a weak reference, if it is still alive
+    ///
+    /// If it was dropped, the weak reference is removed from the store and
+    /// its tokens are invalidated.
+    ///
+    /// Panics if the provided token corresponds to a value that was removed.
+    #[cfg_attr(feature = "provenance", track_caller)]
+    pub fn get_weak<T: 'static>(&mut self, token: &Token<Weak<T>>) -> Option<Rc<T>> {
+        let value = self.get(token).upgrade();
+        if value.is_none() {
+            self.finalize_at(token.id);
+        }
+        value
+    }
+
+    /// Remove all the weak references whose value was dropped
+    ///
+    /// Returns the number of removed weak references.
+    pub fn prune(&mut self) -> usize {
+        if self.weak_types.is_empty() {
+            return 0;
+        }
+        let mut pruned = 0;
+        for id in 0..self.values.len() {
+            if self.is_dead_weak(id) {
+                self.finalize_at(id);
+                pruned += 1;
+            }
+        }
+        pruned
+    }
+
+    // Checks wether a slot holds a weak reference whose value was dropped
+    pub(crate) fn is_dead_weak(&self, id: usize) -> bool {
+        match self.values[id] {
+            Slot {
+                type_id,
+                value: Some(ref boxed),
+                ..
+            } => match self.weak_types.get(&type_id) {
+                Some(is_dead) => is_dead(&**boxed),
+                None => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weak_values() {
+        let mut store = Store::new();
+        let kept = Rc::new(1);
+        let dropped = Rc::new(2);
+        let first = store.insert_weak(&kept);
+        let second = store.insert_weak(&dropped);
+        let third = store.insert_weak(&Rc::new(3));
+        store.insert(4);
+        drop(dropped);
+        assert_eq!(store.get_weak(&first), Some(kept.clone()));
+        assert_eq!(store.get_weak(&third), None);
+        assert!(!third.is_valid());
+        assert_eq!(store.prune(), 1);
+        assert!(!second.is_valid());
+        assert_eq!(store.len(), 2);
+    }
+}